@@ -10,6 +10,10 @@
   slurp,
   grim,
   wayfreeze,
+  tesseract,
+  zbar,
+  wf-recorder,
+  wayland-utils,
 }:
 let
   pname = "shots";
@@ -18,6 +22,7 @@ let
     ./Cargo.lock
     ./Cargo.toml
     ./src
+    ./data
   ];
   src = lib.fileset.toSource {
     root = ./.;
@@ -29,23 +34,32 @@ let
     nativeBuildInputs = [
       pkg-config
       wrapGAppsHook
+      glib
     ];
     buildInputs = [
       gtk4
       gsettings-desktop-schemas
       glib
     ];
+    postInstall = ''
+      install -Dm644 data/com.shots.gschema.xml -t $out/share/gsettings-schemas/$name/glib-2.0/schemas
+      glib-compile-schemas $out/share/gsettings-schemas/$name/glib-2.0/schemas
+    '';
   };
   runtimeInputs = [
     slurp
     grim
     wayfreeze
+    tesseract
+    zbar
+    wf-recorder
+    wayland-utils
   ];
 in
 writeShellApplication {
   name = "shots";
   inherit runtimeInputs;
-  text = "exec \"${shots-unwrapped}/bin/shots\"";
+  text = "exec \"${shots-unwrapped}/bin/shots\" \"$@\"";
   derivationArgs = {
     inherit pname version;
     passthru = {
@@ -11,13 +11,17 @@ use std::{
 
 use anyhow::{Context, Result};
 use gtk4::{
-    gio::{Socket, SocketListener},
-    glib::{Bytes, Object, Priority},
+    gdk::Clipboard,
+    gio::{File, Socket, SocketListener},
+    glib::{clone, MainContext, Object, Priority},
     prelude::{IOStreamExt, SocketListenerExt},
-    ApplicationWindow, CheckButton, Label, Picture, Revealer, SpinButton,
+    ApplicationWindow, CheckButton, DropDown, Label, Picture, Revealer, SpinButton,
 };
 
-use crate::ShotType;
+use crate::{
+    animation::AnimationSettings, burst::BurstSettings, editor::Document, ocr::OcrPanel,
+    recording::RecordingControls, scan_code::CodePanel, ShotType,
+};
 
 pub fn activate_or_open() -> Result<UnixListener> {
     let addr =
@@ -35,15 +39,46 @@ pub fn activate_or_open() -> Result<UnixListener> {
     }
 }
 
+/// The mode of a running recording, burst or scrolling capture, which the next activation stops
+/// whatever mode new captures use.
+fn running_mode(
+    recording_controls: &RecordingControls,
+    burst_settings: &BurstSettings,
+    scrolling: &Cell<bool>,
+) -> Option<ShotType> {
+    // stopping doesn't depend on where the capture was taken
+    if recording_controls.recorder.borrow().is_some() {
+        Some(ShotType::Recording { selection: false })
+    } else if burst_settings.running.borrow().is_some() {
+        Some(ShotType::Burst { selection: false })
+    } else if scrolling.get() {
+        Some(ShotType::Scrolling)
+    } else {
+        None
+    }
+}
+
 pub(crate) async fn wait_for_activation(
+    main_context: MainContext,
     last_shot: Rc<Cell<ShotType>>,
     window: ApplicationWindow,
     listener: UnixListener,
-    image: Rc<RefCell<Option<Bytes>>>,
+    image: Rc<RefCell<Option<Document>>>,
     image_view: Picture,
     image_revealer: Revealer,
+    saved_file: Rc<RefCell<Option<File>>>,
     delay_button: SpinButton,
     cursor_check: CheckButton,
+    freeze_check: CheckButton,
+    format_drop: DropDown,
+    pixel_label: Label,
+    clipboard: Clipboard,
+    ocr_panel: OcrPanel,
+    code_panel: CodePanel,
+    recording_controls: RecordingControls,
+    animation_settings: AnimationSettings,
+    burst_settings: BurstSettings,
+    scrolling: Rc<Cell<bool>>,
     error_revealer: Revealer,
     error_label: Label,
 ) {
@@ -61,7 +96,10 @@ pub(crate) async fn wait_for_activation(
             .close_future(Priority::DEFAULT)
             .await
             .unwrap();
-        match last_shot.get() {
+        let mode = running_mode(&recording_controls, &burst_settings, &scrolling)
+            .or_else(crate::gsettings::default_mode)
+            .unwrap_or_else(|| last_shot.get());
+        match mode {
             ShotType::Fullscreen => {
                 crate::snap_full::handler_inner(
                     &image,
@@ -82,12 +120,133 @@ pub(crate) async fn wait_for_activation(
                     &image_revealer,
                     &delay_button,
                     &cursor_check,
+                    &freeze_check,
+                    &error_revealer,
+                    &error_label,
+                    &window,
+                )
+                .await
+            }
+            ShotType::PickColor => {
+                crate::pick_color::handler_inner(
+                    &delay_button,
+                    &freeze_check,
+                    &format_drop,
+                    &pixel_label,
+                    &clipboard,
+                    &error_revealer,
+                    &error_label,
+                    &window,
+                )
+                .await
+            }
+            ShotType::Text => {
+                crate::ocr::handler_inner(
+                    &ocr_panel,
+                    &delay_button,
+                    &freeze_check,
                     &error_revealer,
                     &error_label,
                     &window,
                 )
                 .await
             }
+            ShotType::Code => {
+                crate::scan_code::handler_inner(
+                    &code_panel,
+                    &delay_button,
+                    &freeze_check,
+                    &error_revealer,
+                    &error_label,
+                    &window,
+                )
+                .await
+            }
+            ShotType::Recording { selection } => {
+                crate::recording::handler_inner(
+                    selection,
+                    &recording_controls,
+                    &image,
+                    &image_view,
+                    &image_revealer,
+                    &delay_button,
+                    &error_revealer,
+                    &error_label,
+                    &window,
+                )
+                .await
+            }
+            ShotType::Animation => {
+                crate::animation::handler_inner(
+                    &animation_settings,
+                    &recording_controls,
+                    &image,
+                    &image_view,
+                    &image_revealer,
+                    &delay_button,
+                    &error_revealer,
+                    &error_label,
+                    &window,
+                )
+                .await
+            }
+            ShotType::Burst { selection } => {
+                // runs alongside the loop so that the next activation can stop it
+                main_context.spawn_local(clone!(
+                    @strong burst_settings,
+                    @strong image,
+                    @strong image_view,
+                    @strong image_revealer,
+                    @strong saved_file,
+                    @strong delay_button,
+                    @strong cursor_check,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @strong window
+                        => async move {
+                            crate::burst::handler_inner(
+                                selection,
+                                &burst_settings,
+                                &image,
+                                &image_view,
+                                &image_revealer,
+                                &saved_file,
+                                &delay_button,
+                                &cursor_check,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+            ShotType::Scrolling => {
+                // runs alongside the loop so that the next activation can finish it
+                main_context.spawn_local(clone!(
+                    @strong scrolling,
+                    @strong image,
+                    @strong image_view,
+                    @strong image_revealer,
+                    @strong delay_button,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @strong window
+                        => async move {
+                            crate::scroll_capture::handler_inner(
+                                &scrolling,
+                                &image,
+                                &image_view,
+                                &image_revealer,
+                                &delay_button,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
         };
     }
 }
@@ -0,0 +1,509 @@
+use std::{
+    cell::{Cell, RefCell},
+    ffi::OsStr,
+    fs::write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gdk::{Paintable, Texture},
+    gio,
+    glib::{self, clone, timeout_future, Cast, MainContext},
+    prelude::{TextureExt, WidgetExt},
+    ApplicationWindow, Button, DropDown, Label, Picture, Revealer, SpinButton,
+};
+
+use crate::{
+    auto_save::TempFile,
+    command::{run, select_region},
+    editor::{resize, surface_from_texture, texture_from_surface, Document},
+    inspect::straight_rgba,
+    recording::RecordingControls,
+    ShotType,
+};
+
+const MAX_FRAMES: usize = 300;
+/// Frames are scaled down to fit, which keeps files small enough to paste into chats.
+const MAX_DIMENSION: i32 = 800;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub(crate) const NAMES: [&'static str; 2] = ["GIF", "APNG"];
+
+    fn from_selected(format_drop: &DropDown) -> Self {
+        match format_drop.selected() {
+            1 => AnimationFormat::Apng,
+            _ => AnimationFormat::Gif,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct AnimationSettings {
+    pub(crate) fps: SpinButton,
+    pub(crate) seconds: SpinButton,
+    pub(crate) format_drop: DropDown,
+}
+
+struct Frame {
+    texture: Texture,
+    delay_ms: u32,
+}
+
+/// Captures the region repeatedly, the delay of each frame is the time until the next one.
+async fn capture_frames(region: &str, fps: f64, seconds: f64) -> Result<Vec<(Texture, Instant)>> {
+    let count = ((fps * seconds).round() as usize).clamp(1, MAX_FRAMES);
+    let interval = Duration::from_secs_f64(1.0 / fps);
+    let start = Instant::now();
+    let mut captures = Vec::with_capacity(count);
+    for index in 0..count {
+        let target = start + interval * index as u32;
+        if let Some(wait) = target.checked_duration_since(Instant::now()) {
+            timeout_future(wait).await;
+        }
+        let captured_at = Instant::now();
+        let png = run(
+            &[
+                OsStr::new("grim"),
+                OsStr::new("-g"),
+                OsStr::new(region),
+                OsStr::new("-"),
+            ],
+            None,
+        )
+        .await?;
+        let texture = Texture::from_bytes(&png).context("decoding captured frame")?;
+        captures.push((texture, captured_at));
+    }
+    Ok(captures)
+}
+
+/// Scales frames down to the size limit and merges frames identical to their predecessor.
+fn optimize(captures: Vec<(Texture, Instant)>, interval: Duration) -> Result<Vec<Frame>> {
+    let times: Vec<Instant> = captures.iter().map(|(_, time)| *time).collect();
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut previous_data: Option<Vec<u8>> = None;
+    for (index, (texture, time)) in captures.into_iter().enumerate() {
+        let delay = times
+            .get(index + 1)
+            .map_or(interval, |next| next.duration_since(time));
+        let delay_ms = delay.as_millis().max(10) as u32;
+
+        let mut surface = surface_from_texture(&texture)?;
+        let (width, height) = (surface.width(), surface.height());
+        let factor = (MAX_DIMENSION as f64 / width.max(height) as f64).min(1.0);
+        if factor < 1.0 {
+            surface = resize(
+                &surface,
+                ((width as f64 * factor).round() as i32).max(1),
+                ((height as f64 * factor).round() as i32).max(1),
+            )?;
+        }
+        let data = surface.data().context("accessing frame")?.to_vec();
+        if previous_data.as_ref() == Some(&data) {
+            if let Some(frame) = frames.last_mut() {
+                frame.delay_ms += delay_ms;
+            }
+            continue;
+        }
+        previous_data = Some(data);
+        frames.push(Frame {
+            texture: texture_from_surface(surface)?,
+            delay_ms,
+        });
+    }
+    Ok(frames)
+}
+
+fn rgba(texture: &Texture) -> Result<Vec<u8>> {
+    let mut surface = surface_from_texture(texture)?;
+    let (width, stride) = (surface.width() as usize, surface.stride() as usize);
+    let data = surface.data().context("accessing frame")?;
+    Ok(data
+        .chunks(stride)
+        .flat_map(|row| {
+            row[..width * 4]
+                .as_chunks::<4>()
+                .0
+                .iter()
+                .flat_map(|pixel| straight_rgba(*pixel))
+        })
+        .collect())
+}
+
+fn encode_gif(width: u16, height: u16, frames: Vec<(Vec<u8>, u32)>) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    {
+        let mut encoder =
+            gif::Encoder::new(&mut out, width, height, &[]).context("creating GIF encoder")?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .context("writing GIF header")?;
+        for (mut rgba, delay_ms) in frames {
+            // speed 10 is gif's recommended trade-off between quality and encoding time
+            let mut frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+            frame.delay = (delay_ms / 10).clamp(1, u16::MAX as u32) as u16;
+            encoder.write_frame(&frame).context("writing GIF frame")?;
+        }
+    }
+    Ok(out)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn chunks(png: &[u8]) -> Result<Vec<([u8; 4], &[u8])>> {
+    let mut rest = png
+        .strip_prefix(&PNG_SIGNATURE)
+        .ok_or_else(|| anyhow!("frame is not a PNG image"))?;
+    let mut chunks = Vec::new();
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes(rest[..4].try_into()?) as usize;
+        let kind: [u8; 4] = rest[4..8].try_into()?;
+        let (data, next) = rest
+            .get(8..8 + length)
+            .zip(rest.get(12 + length..))
+            .ok_or_else(|| anyhow!("truncated PNG chunk"))?;
+        chunks.push((kind, data));
+        rest = next;
+    }
+    Ok(chunks)
+}
+
+/// Assembles an APNG from PNG encoded frames of the same size, reusing their image data.
+fn encode_apng(width: u32, height: u32, frames: &[(Vec<u8>, u32)]) -> Result<Vec<u8>> {
+    let mut out = PNG_SIGNATURE.to_vec();
+    let mut sequence = 0u32;
+    for (index, (png, delay_ms)) in frames.iter().enumerate() {
+        let mut control = Vec::with_capacity(26);
+        control.extend_from_slice(&sequence.to_be_bytes());
+        control.extend_from_slice(&width.to_be_bytes());
+        control.extend_from_slice(&height.to_be_bytes());
+        control.extend_from_slice(&[0; 8]);
+        control.extend_from_slice(&((*delay_ms).min(u16::MAX as u32) as u16).to_be_bytes());
+        control.extend_from_slice(&1000u16.to_be_bytes());
+        control.extend_from_slice(&[0, 0]);
+        sequence += 1;
+
+        let mut control_written = false;
+        for (kind, data) in chunks(png)? {
+            match &kind {
+                b"IHDR" if index == 0 => {
+                    push_chunk(&mut out, &kind, data);
+                    let mut animation = (frames.len() as u32).to_be_bytes().to_vec();
+                    animation.extend_from_slice(&0u32.to_be_bytes());
+                    push_chunk(&mut out, b"acTL", &animation);
+                }
+                b"IDAT" => {
+                    if !control_written {
+                        push_chunk(&mut out, b"fcTL", &control);
+                        control_written = true;
+                    }
+                    if index == 0 {
+                        push_chunk(&mut out, &kind, data);
+                    } else {
+                        let mut frame_data = sequence.to_be_bytes().to_vec();
+                        frame_data.extend_from_slice(data);
+                        push_chunk(&mut out, b"fdAT", &frame_data);
+                        sequence += 1;
+                    }
+                }
+                b"IEND" => {}
+                // keep colour information of the first frame, it applies to all frames
+                _ if index == 0 => push_chunk(&mut out, &kind, data),
+                _ => {}
+            }
+        }
+    }
+    push_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+
+async fn encode(frames: &[Frame], format: AnimationFormat) -> Result<Vec<u8>> {
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow!("No frames were captured"))?;
+    let (width, height) = (first.texture.width(), first.texture.height());
+    match format {
+        AnimationFormat::Gif => {
+            let frames = frames
+                .iter()
+                .map(|frame| Ok((rgba(&frame.texture)?, frame.delay_ms)))
+                .collect::<Result<Vec<_>>>()?;
+            gio::spawn_blocking(move || encode_gif(width as u16, height as u16, frames))
+                .await
+                .map_err(|_| anyhow!("encoding GIF panicked"))?
+        }
+        AnimationFormat::Apng => {
+            let frames: Vec<(Vec<u8>, u32)> = frames
+                .iter()
+                .map(|frame| (frame.texture.save_to_png_bytes().to_vec(), frame.delay_ms))
+                .collect();
+            gio::spawn_blocking(move || encode_apng(width as u32, height as u32, &frames))
+                .await
+                .map_err(|_| anyhow!("encoding APNG panicked"))?
+        }
+    }
+}
+
+async fn record_animation(
+    settings: &AnimationSettings,
+    wait_seconds: u32,
+) -> Result<(Vec<Frame>, TempFile)> {
+    timeout_future(Duration::from_secs(wait_seconds.into())).await;
+
+    let region = select_region().await?;
+    let fps = settings.fps.value();
+    let captures = capture_frames(&region, fps, settings.seconds.value()).await?;
+    let frames = optimize(captures, Duration::from_secs_f64(1.0 / fps))?;
+
+    let format = AnimationFormat::from_selected(&settings.format_drop);
+    let encoded = encode(&frames, format).await?;
+    let file = TempFile::new(format.extension())?;
+    write(file.path(), encoded).context("writing animation")?;
+    Ok((frames, file))
+}
+
+/// Shows the frames in a loop until something else is shown in the preview.
+fn play(image_view: &Picture, frames: Rc<Vec<Frame>>, index: usize) {
+    let frame = &frames[index];
+    image_view.set_paintable(Some(&frame.texture));
+    let delay = Duration::from_millis(frame.delay_ms.into());
+    glib::timeout_add_local_once(
+        delay,
+        clone!(@weak image_view, @strong frames => move || {
+            let shown = image_view.paintable();
+            if shown.as_ref() == Some(frames[index].texture.upcast_ref::<Paintable>()) {
+                play(&image_view, frames.clone(), (index + 1) % frames.len());
+            }
+        }),
+    );
+}
+
+pub(crate) async fn handler_inner(
+    settings: &AnimationSettings,
+    controls: &RecordingControls,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    delay_button: &SpinButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) {
+    match record_animation(settings, delay_button.value() as u32).await {
+        Ok((frames, file)) => {
+            // the preview no longer shows a screenshot that could be edited or saved
+            image.replace(None);
+            play(image_view, Rc::new(frames), 0);
+            controls.recorded.replace(Some(file));
+            controls.revealer.set_reveal_child(true);
+            image_revealer.set_reveal_child(true);
+            error_revealer.set_reveal_child(false);
+        }
+        Err(e) => {
+            error_label.set_text(&format!("{:?}", e));
+            error_revealer.set_reveal_child(true);
+        }
+    }
+    window.set_visible(true);
+}
+
+pub(crate) fn get_handler(
+    last_shot: &Rc<Cell<ShotType>>,
+    main_context: &MainContext,
+    settings: &AnimationSettings,
+    controls: &RecordingControls,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    delay_button: &SpinButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong last_shot,
+        @strong main_context,
+        @strong settings,
+        @strong controls,
+        @strong image,
+        @strong image_view,
+        @strong image_revealer,
+        @strong delay_button,
+        @strong error_revealer,
+        @strong error_label,
+        @weak window
+            => move |_| {
+                window.set_visible(false);
+                last_shot.set(ShotType::Animation);
+                main_context.spawn_local(clone!(
+                    @strong settings,
+                    @strong controls,
+                    @strong image,
+                    @strong image_view,
+                    @strong image_revealer,
+                    @strong delay_button,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @weak window
+                        => async move {
+                            handler_inner(
+                                &settings,
+                                &controls,
+                                &image,
+                                &image_view,
+                                &image_revealer,
+                                &delay_button,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PNG with the given chunks, the image data is not looked at.
+    fn png(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut out = PNG_SIGNATURE.to_vec();
+        for (kind, data) in chunks {
+            push_chunk(&mut out, kind, data);
+        }
+        out
+    }
+
+    fn be_u32(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes(bytes[..4].try_into().unwrap())
+    }
+
+    #[test]
+    fn crc32_matches_png() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn push_chunk_writes_length_kind_data_and_crc() {
+        let mut out = Vec::new();
+        push_chunk(&mut out, b"IEND", &[]);
+        assert_eq!(
+            out,
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn chunks_rejects_truncated_images() {
+        assert!(chunks(b"GIF89a").is_err());
+        let mut image = png(&[(b"IDAT", &[1, 2, 3])]);
+        image.truncate(image.len() - 1);
+        assert!(chunks(&image).is_err());
+    }
+
+    #[test]
+    fn encode_apng_numbers_frames() {
+        let header = [0, 0, 0, 2, 0, 0, 0, 1, 8, 6, 0, 0, 0];
+        let first = png(&[
+            (b"IHDR", &header),
+            (b"sRGB", &[0]),
+            (b"IDAT", &[1, 1]),
+            (b"IEND", &[]),
+        ]);
+        let second = png(&[
+            (b"IHDR", &header),
+            (b"sRGB", &[0]),
+            (b"IDAT", &[2, 2]),
+            (b"IDAT", &[3]),
+            (b"IEND", &[]),
+        ]);
+        let apng = encode_apng(2, 1, &[(first, 40), (second, 70)]).unwrap();
+
+        let chunks = chunks(&apng).unwrap();
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| &kind[..]).collect();
+        assert_eq!(
+            kinds,
+            [
+                &b"IHDR"[..],
+                b"acTL",
+                b"sRGB",
+                b"fcTL",
+                b"IDAT",
+                b"fcTL",
+                b"fdAT",
+                b"fdAT",
+                b"IEND"
+            ]
+        );
+        assert!(apng.ends_with(&[0xae, 0x42, 0x60, 0x82]));
+
+        let (_, header_data) = chunks[0];
+        assert_eq!(header_data, header);
+        // two frames, played forever
+        let (_, animation) = chunks[1];
+        assert_eq!(animation, [0, 0, 0, 2, 0, 0, 0, 0]);
+
+        // sequence, width, height, x and y offset, delay in milliseconds, disposal and blending
+        let (_, control) = chunks[3];
+        assert_eq!(control.len(), 26);
+        assert_eq!(be_u32(control), 0);
+        assert_eq!(be_u32(&control[4..]), 2);
+        assert_eq!(be_u32(&control[8..]), 1);
+        assert_eq!(&control[12..20], [0; 8]);
+        assert_eq!(&control[20..24], [0, 40, 0x03, 0xe8]);
+        assert_eq!(&control[24..], [0, 0]);
+        assert_eq!(chunks[4].1, [1, 1]);
+
+        let (_, control) = chunks[5];
+        assert_eq!(be_u32(control), 1);
+        assert_eq!(&control[20..24], [0, 70, 0x03, 0xe8]);
+        let (_, data) = chunks[6];
+        assert_eq!(be_u32(data), 2);
+        assert_eq!(&data[4..], [2, 2]);
+        let (_, data) = chunks[7];
+        assert_eq!(be_u32(data), 3);
+        assert_eq!(&data[4..], [3]);
+    }
+}
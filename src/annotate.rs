@@ -0,0 +1,378 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use anyhow::{Context, Result};
+use gtk4::{
+    gdk::Rectangle,
+    glib::{self, clone},
+    prelude::{
+        DrawingAreaExtManual, EditableExt, EntryExt, GestureDragExt, GestureExt, PopoverExt,
+        WidgetExt,
+    },
+    ColorDialogButton, DrawingArea, DropDown, Entry, EventSequenceState, GestureDrag, Label,
+    Picture, Popover, Revealer, SpinButton,
+};
+
+use crate::{
+    editor::{show_texture, Document, Operation, Point, Stroke},
+    redact::Redaction,
+};
+
+const REDACTION_SCALE: f64 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tool {
+    None,
+    Arrow,
+    Rectangle,
+    Ellipse,
+    Line,
+    Pen,
+    Text,
+    Step,
+    Magnify,
+    Pixelate,
+    Blur,
+    Blackout,
+    Crop,
+    Inspect,
+    Measure,
+    Pan,
+}
+
+impl Tool {
+    pub(crate) const ALL: [Tool; 16] = [
+        Tool::None,
+        Tool::Arrow,
+        Tool::Rectangle,
+        Tool::Ellipse,
+        Tool::Line,
+        Tool::Pen,
+        Tool::Text,
+        Tool::Step,
+        Tool::Magnify,
+        Tool::Pixelate,
+        Tool::Blur,
+        Tool::Blackout,
+        Tool::Crop,
+        Tool::Inspect,
+        Tool::Measure,
+        Tool::Pan,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Tool::None => "None",
+            Tool::Arrow => "Arrow",
+            Tool::Rectangle => "Rectangle",
+            Tool::Ellipse => "Ellipse",
+            Tool::Line => "Line",
+            Tool::Pen => "Pen",
+            Tool::Text => "Text",
+            Tool::Step => "Step Number",
+            Tool::Magnify => "Magnifier",
+            Tool::Pixelate => "Pixelate",
+            Tool::Blur => "Blur",
+            Tool::Blackout => "Blackout",
+            Tool::Crop => "Crop",
+            Tool::Inspect => "Colour Picker",
+            Tool::Measure => "Ruler",
+            Tool::Pan => "Pan",
+        }
+    }
+
+    pub(crate) fn names() -> Vec<&'static str> {
+        Tool::ALL.iter().map(|tool| tool.name()).collect()
+    }
+
+    pub(crate) fn from_selected(tool_drop: &DropDown) -> Self {
+        Tool::ALL
+            .get(tool_drop.selected() as usize)
+            .copied()
+            .unwrap_or(Tool::None)
+    }
+
+    fn is_annotation(self) -> bool {
+        !matches!(
+            self,
+            Tool::None | Tool::Crop | Tool::Inspect | Tool::Measure | Tool::Pan
+        )
+    }
+
+    fn start(self, point: Point, stroke: Stroke) -> Option<Operation> {
+        match self {
+            Tool::None
+            | Tool::Text
+            | Tool::Step
+            | Tool::Crop
+            | Tool::Inspect
+            | Tool::Measure
+            | Tool::Pan => None,
+            Tool::Arrow => Some(Operation::Arrow {
+                start: point,
+                end: point,
+                stroke,
+            }),
+            Tool::Rectangle | Tool::Magnify => Some(Operation::Rectangle {
+                start: point,
+                end: point,
+                stroke,
+            }),
+            Tool::Ellipse => Some(Operation::Ellipse {
+                start: point,
+                end: point,
+                stroke,
+            }),
+            Tool::Line => Some(Operation::Line {
+                start: point,
+                end: point,
+                stroke,
+            }),
+            Tool::Pen => Some(Operation::Pen {
+                points: vec![point],
+                stroke,
+            }),
+            Tool::Pixelate => Some(redaction(point, Redaction::Pixelate, stroke)),
+            Tool::Blur => Some(redaction(point, Redaction::Blur, stroke)),
+            Tool::Blackout => Some(redaction(point, Redaction::Blackout, stroke)),
+        }
+    }
+}
+
+fn redaction(point: Point, kind: Redaction, stroke: Stroke) -> Operation {
+    Operation::Redact {
+        start: point,
+        end: point,
+        kind,
+        size: (stroke.width * REDACTION_SCALE) as usize,
+    }
+}
+
+fn stroke(color_button: &ColorDialogButton, width_button: &SpinButton) -> Stroke {
+    Stroke {
+        color: color_button.rgba(),
+        width: width_button.value(),
+    }
+}
+
+fn apply(
+    operation: Operation,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+) -> Result<()> {
+    if let Some(document) = image.borrow_mut().as_mut() {
+        document.push(operation).context("applying annotation")?;
+        show_texture(image_view, document.texture());
+    }
+    Ok(())
+}
+
+/// Only lets `layer` receive input while one of the tools it handles is selected,
+/// so events reach the layer belonging to the current tool.
+pub(crate) fn target_tools(layer: &DrawingArea, tool_drop: &DropDown, handles: fn(Tool) -> bool) {
+    layer.set_can_target(handles(Tool::from_selected(tool_drop)));
+    tool_drop.connect_selected_notify(clone!(@weak layer => move |tool_drop| {
+        layer.set_can_target(handles(Tool::from_selected(tool_drop)));
+    }));
+}
+
+/// Removes the step callout under `point` or places the next one there.
+fn place_step(
+    point: Point,
+    stroke: Stroke,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+) -> Result<()> {
+    if let Some(document) = image.borrow_mut().as_mut() {
+        if !document.remove_step_at(point).context("removing step")? {
+            let number = document.next_step();
+            document
+                .push(Operation::Step {
+                    center: point,
+                    number,
+                    stroke,
+                })
+                .context("placing step")?;
+        }
+        show_texture(image_view, document.texture());
+    }
+    Ok(())
+}
+
+pub(crate) fn show_error(e: anyhow::Error, error_revealer: &Revealer, error_label: &Label) {
+    error_label.set_text(&format!("{e:?}"));
+    error_revealer.set_reveal_child(true);
+}
+
+pub(crate) fn connect_canvas(
+    canvas: &DrawingArea,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    tool_drop: &DropDown,
+    color_button: &ColorDialogButton,
+    width_button: &SpinButton,
+    magnification_button: &SpinButton,
+    scale: &Rc<Cell<f64>>,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) {
+    let pending: Rc<RefCell<Option<Operation>>> = Rc::new(RefCell::new(None));
+    let text_position: Rc<Cell<Point>> = Rc::new(Cell::new((0.0, 0.0)));
+    // area selected with the magnifier tool, waiting for a click placing the inset
+    let magnify_source: Rc<RefCell<Option<Operation>>> = Rc::new(RefCell::new(None));
+
+    target_tools(canvas, tool_drop, Tool::is_annotation);
+    tool_drop.connect_selected_notify(clone!(@strong magnify_source, @weak canvas => move |_| {
+        magnify_source.replace(None);
+        canvas.queue_draw();
+    }));
+
+    canvas.set_draw_func(
+        clone!(@strong pending, @strong magnify_source, @strong scale => move |_, cr, _, _| {
+            cr.scale(scale.get(), scale.get());
+            for operation in [&pending, &magnify_source] {
+                if let Some(operation) = operation.borrow().as_ref() {
+                    let _ = operation.draw(cr);
+                }
+            }
+        }),
+    );
+
+    let text_entry = Entry::builder().placeholder_text("Annotation text").build();
+    let text_popover = Popover::builder().child(&text_entry).build();
+    text_popover.set_parent(canvas);
+    text_entry.connect_activate(clone!(
+        @strong image,
+        @weak image_view,
+        @strong text_position,
+        @weak text_popover,
+        @strong color_button,
+        @strong width_button,
+        @strong error_revealer,
+        @strong error_label
+            => move |text_entry| {
+                let text = text_entry.text();
+                if !text.is_empty() {
+                    let operation = Operation::Text {
+                        position: text_position.get(),
+                        text: text.to_string(),
+                        stroke: stroke(&color_button, &width_button),
+                    };
+                    if let Err(e) = apply(operation, &image, &image_view) {
+                        show_error(e, &error_revealer, &error_label);
+                    }
+                }
+                text_entry.set_text("");
+                text_popover.popdown();
+            }
+    ));
+
+    let drag = GestureDrag::new();
+    drag.connect_drag_begin(clone!(
+        @strong pending,
+        @strong magnify_source,
+        @strong tool_drop,
+        @strong color_button,
+        @strong width_button,
+        @strong scale
+            => move |drag, x, y| {
+                let tool = Tool::from_selected(&tool_drop);
+                if !tool.is_annotation() {
+                    drag.set_state(EventSequenceState::Denied);
+                    return;
+                }
+                if tool == Tool::Magnify && magnify_source.borrow().is_some() {
+                    return;
+                }
+                let point = (x / scale.get(), y / scale.get());
+                pending.replace(tool.start(point, stroke(&color_button, &width_button)));
+            }
+    ));
+    drag.connect_drag_update(clone!(
+        @strong pending,
+        @strong scale,
+        @weak canvas
+            => move |drag, x, y| {
+                if let (Some(operation), Some((start_x, start_y))) =
+                    (pending.borrow_mut().as_mut(), drag.start_point())
+                {
+                    let scale = scale.get();
+                    operation.drag_to(((start_x + x) / scale, (start_y + y) / scale));
+                }
+                canvas.queue_draw();
+            }
+    ));
+    drag.connect_drag_end(clone!(
+        @strong pending,
+        @strong image,
+        @weak image_view,
+        @weak canvas,
+        @strong tool_drop,
+        @strong color_button,
+        @strong width_button,
+        @strong magnification_button,
+        @strong magnify_source,
+        @strong text_position,
+        @weak text_popover,
+        @strong scale,
+        @strong error_revealer,
+        @strong error_label
+            => move |drag, _, _| {
+                let Some((start_x, start_y)) = drag.start_point() else {
+                    return;
+                };
+                let point = (start_x / scale.get(), start_y / scale.get());
+                let tool = Tool::from_selected(&tool_drop);
+                let result = if let Some(operation) = pending.take() {
+                    if tool == Tool::Magnify {
+                        magnify_source.replace(Some(operation));
+                        Ok(())
+                    } else {
+                        apply(operation, &image, &image_view)
+                    }
+                } else {
+                    match tool {
+                        Tool::Magnify => match magnify_source.take() {
+                            Some(Operation::Rectangle { start, end, stroke }) => apply(
+                                Operation::Magnify {
+                                    start,
+                                    end,
+                                    destination: point,
+                                    factor: magnification_button.value(),
+                                    stroke,
+                                },
+                                &image,
+                                &image_view,
+                            ),
+                            _ => Ok(()),
+                        },
+                        Tool::Text => {
+                            text_position.set(point);
+                            text_popover.set_pointing_to(Some(&Rectangle::new(
+                                start_x as i32,
+                                start_y as i32,
+                                1,
+                                1,
+                            )));
+                            text_popover.popup();
+                            Ok(())
+                        }
+                        Tool::Step => place_step(
+                            point,
+                            stroke(&color_button, &width_button),
+                            &image,
+                            &image_view,
+                        ),
+                        _ => Ok(()),
+                    }
+                };
+                if let Err(e) = result {
+                    show_error(e, &error_revealer, &error_label);
+                }
+                canvas.queue_draw();
+            }
+    ));
+    canvas.add_controller(drag);
+}
@@ -0,0 +1,73 @@
+use std::{
+    cell::RefCell,
+    fs::{remove_file, File},
+    os::fd::FromRawFd,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use gtk4::glib::{self, DateTime, UserDirectory};
+
+thread_local! {
+    // set from the preferences, read wherever captures are saved
+    static SAVE_DIRECTORY: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Names files after the time of the capture, e.g. `shots-2024-01-31_12-00-00`.
+pub(crate) fn file_stem(time: &DateTime) -> Result<String> {
+    Ok(time
+        .format("shots-%Y-%m-%d_%H-%M-%S")
+        .context("formatting file name")?
+        .to_string())
+}
+
+/// Numbers files belonging to one sequence of captures, so they sort in capture order.
+pub(crate) fn sequence_name(stem: &str, index: u32, extension: &str) -> String {
+    format!("{stem}-{index:03}.{extension}")
+}
+
+/// A file in the temporary directory that is removed again when dropped.
+#[derive(Debug)]
+pub(crate) struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Creates an empty file with a unique name starting with the current time.
+    pub(crate) fn new(extension: &str) -> Result<Self> {
+        let now = DateTime::now_local().context("getting current time")?;
+        let template = format!("{}-XXXXXX.{extension}", file_stem(&now)?);
+        let (fd, path) =
+            glib::file_open_tmp(Some(template.as_str())).context("creating temporary file")?;
+        // SAFETY: the descriptor was just opened for us and is not used elsewhere
+        drop(unsafe { File::from_raw_fd(fd) });
+        Ok(TempFile { path })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+/// Empty to save to the pictures directory.
+pub(crate) fn set_directory(directory: &str) {
+    let directory = (!directory.is_empty()).then(|| PathBuf::from(directory));
+    SAVE_DIRECTORY.with(|current| current.replace(directory));
+}
+
+pub(crate) fn pictures_directory() -> PathBuf {
+    glib::user_special_dir(UserDirectory::Pictures).unwrap_or_else(glib::home_dir)
+}
+
+/// Where captures are saved, the configured save directory or else the pictures directory.
+pub(crate) fn directory() -> PathBuf {
+    SAVE_DIRECTORY
+        .with(|current| current.borrow().clone())
+        .unwrap_or_else(pictures_directory)
+}
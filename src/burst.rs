@@ -0,0 +1,213 @@
+use std::{
+    cell::{Cell, RefCell},
+    ffi::OsStr,
+    fs::{create_dir_all, write},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use gtk4::{
+    gio::{Cancellable, CancellableFuture, File},
+    glib::{self, clone, timeout_future, Bytes, DateTime, MainContext},
+    prelude::{CancellableExt, CheckButtonExt, WidgetExt},
+    ApplicationWindow, Button, CheckButton, Label, Picture, Revealer, SpinButton,
+};
+
+use crate::{
+    auto_save::{directory, file_stem, sequence_name},
+    command::{run, select_region},
+    editor::{show_texture, Document},
+    ShotType,
+};
+
+/// How many captures a burst takes and how far apart, a second activation stops the burst
+/// by cancelling `running`.
+#[derive(Clone)]
+pub(crate) struct BurstSettings {
+    pub(crate) count: SpinButton,
+    pub(crate) interval: SpinButton,
+    pub(crate) running: Rc<RefCell<Option<Cancellable>>>,
+}
+
+/// Captures the region, or the whole screen without one.
+async fn capture(region: Option<&str>, cursor: bool) -> Result<Bytes> {
+    let mut argv = vec![OsStr::new("grim")];
+    if let Some(region) = region {
+        argv.extend([OsStr::new("-g"), OsStr::new(region)]);
+    }
+    if cursor {
+        argv.push(OsStr::new("-c"));
+    }
+    argv.push(OsStr::new("-"));
+    run(&argv, None).await
+}
+
+/// Saves every capture as a numbered file and returns the last one.
+async fn burst(
+    selection: bool,
+    settings: &BurstSettings,
+    cancellable: &Cancellable,
+    cursor: bool,
+    wait_seconds: u32,
+) -> Result<Option<(Bytes, File)>> {
+    let delay = timeout_future(Duration::from_secs(wait_seconds.into()));
+    if CancellableFuture::new(delay, cancellable.clone())
+        .await
+        .is_err()
+    {
+        return Ok(None);
+    }
+
+    // asked for once, the burst then captures the same region every time
+    let region = if selection {
+        Some(select_region().await?)
+    } else {
+        None
+    };
+    let count = settings.count.value() as u32;
+    let interval = Duration::from_secs_f64(settings.interval.value());
+    let folder = directory();
+    create_dir_all(&folder).context("creating pictures directory")?;
+    let stem = file_stem(&DateTime::now_local().context("getting current time")?)?;
+
+    let start = Instant::now();
+    let mut last = None;
+    for index in 0..count {
+        let target = start + interval * index;
+        let wait = timeout_future(target.saturating_duration_since(Instant::now()));
+        // stops right away when cancelled, also in the middle of a long interval
+        if CancellableFuture::new(wait, cancellable.clone())
+            .await
+            .is_err()
+        {
+            break;
+        }
+        let png = capture(region.as_deref(), cursor).await?;
+        let path = folder.join(sequence_name(&stem, index + 1, "png"));
+        write(&path, &png).context("writing capture")?;
+        last = Some((png, File::for_path(path)));
+    }
+    Ok(last)
+}
+
+/// Starts a burst, or stops the running one once a capture in progress is saved.
+pub(crate) async fn handler_inner(
+    selection: bool,
+    settings: &BurstSettings,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    saved_file: &Rc<RefCell<Option<File>>>,
+    delay_button: &SpinButton,
+    cursor_check: &CheckButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) {
+    if let Some(running) = settings.running.take() {
+        running.cancel();
+        return;
+    }
+    let cancellable = Cancellable::new();
+    settings.running.replace(Some(cancellable.clone()));
+    window.set_visible(false);
+    let result = burst(
+        selection,
+        settings,
+        &cancellable,
+        cursor_check.is_active(),
+        delay_button.value() as u32,
+    )
+    .await
+    .and_then(|last| {
+        last.map(|(png, file)| {
+            let document = Document::new(png)?;
+            let texture = document.texture().clone();
+            image.replace(Some(document));
+            saved_file.replace(Some(file));
+            Ok(texture)
+        })
+        .transpose()
+    });
+    // a stopped burst may already have been followed by a new one
+    if settings.running.borrow().as_ref() == Some(&cancellable) {
+        settings.running.replace(None);
+    }
+    match result {
+        Ok(texture) => {
+            if let Some(texture) = texture {
+                show_texture(image_view, &texture);
+                image_revealer.set_reveal_child(true);
+            }
+            error_revealer.set_reveal_child(false);
+        }
+        Err(e) => {
+            error_label.set_text(&format!("{:?}", e));
+            error_revealer.set_reveal_child(true);
+        }
+    }
+    window.set_visible(true);
+}
+
+pub(crate) fn get_handler(
+    selection: bool,
+    last_shot: &Rc<Cell<ShotType>>,
+    main_context: &MainContext,
+    settings: &BurstSettings,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    saved_file: &Rc<RefCell<Option<File>>>,
+    delay_button: &SpinButton,
+    cursor_check: &CheckButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong last_shot,
+        @strong main_context,
+        @strong settings,
+        @strong image,
+        @strong image_view,
+        @strong image_revealer,
+        @strong saved_file,
+        @strong delay_button,
+        @strong cursor_check,
+        @strong error_revealer,
+        @strong error_label,
+        @weak window
+            => move |_| {
+                last_shot.set(ShotType::Burst { selection });
+                main_context.spawn_local(clone!(
+                    @strong settings,
+                    @strong image,
+                    @strong image_view,
+                    @strong image_revealer,
+                    @strong saved_file,
+                    @strong delay_button,
+                    @strong cursor_check,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @weak window
+                        => async move {
+                            handler_inner(
+                                selection,
+                                &settings,
+                                &image,
+                                &image_view,
+                                &image_revealer,
+                                &saved_file,
+                                &delay_button,
+                                &cursor_check,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+    )
+}
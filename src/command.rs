@@ -0,0 +1,64 @@
+use std::{
+    ffi::{OsStr, OsString},
+    str::from_utf8,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gio::{Subprocess, SubprocessFlags},
+    glib::{self, Bytes},
+};
+
+use crate::tools::command_line;
+
+/// Explains a failed spawn, pointing at `shots --check` when the program is not installed.
+pub(crate) fn spawn_context(program: &OsStr) -> String {
+    let name = program.to_string_lossy();
+    if glib::find_program_in_path(program).is_none() {
+        format!("{name} was not found, install it or run `shots --check` to see what is missing")
+    } else {
+        format!("spawning {name}")
+    }
+}
+
+/// Runs `argv` with the configured tool paths and arguments to completion, optionally feeding `stdin`, and returns its standard output.
+pub(crate) async fn run(argv: &[&OsStr], stdin: Option<&Bytes>) -> Result<Bytes> {
+    let name = argv[0].to_string_lossy();
+    let mut flags = SubprocessFlags::STDOUT_PIPE | SubprocessFlags::STDERR_PIPE;
+    if stdin.is_some() {
+        flags |= SubprocessFlags::STDIN_PIPE;
+    }
+    let argv = command_line(argv);
+    let argv: Vec<&OsStr> = argv.iter().map(OsString::as_os_str).collect();
+    let process = Subprocess::newv(&argv, flags).with_context(|| spawn_context(argv[0]))?;
+    let (out, err) = process
+        .communicate_future(stdin)
+        .await
+        .with_context(|| format!("receiving output from {name}"))?;
+    if process.is_successful() {
+        Ok(out.expect("stdout output"))
+    } else {
+        let err = err.expect("stderr output");
+        if err.is_empty() {
+            Err(anyhow!(
+                "{name} failed with exit status {} but no error output was provided",
+                process.exit_status()
+            ))
+        } else {
+            Err(anyhow!(
+                "{name} failed with exit status {}:\n{}",
+                process.exit_status(),
+                from_utf8(&err).with_context(|| format!("decoding {name} stderr output"))?
+            ))
+        }
+    }
+}
+
+/// Lets the user select a region with slurp, in the format grim and wf-recorder take with `-g`.
+pub(crate) async fn select_region() -> Result<String> {
+    let region = run(&[OsStr::new("slurp")], None).await?;
+    Ok(from_utf8(&region)
+        .context("decoding slurp output")?
+        .trim_end()
+        .to_string())
+}
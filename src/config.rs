@@ -0,0 +1,719 @@
+use std::{
+    cell::{Cell, RefCell},
+    fs::{create_dir_all, read_to_string, write},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gdk::RGBA,
+    gio::{self, FileMonitor, FileMonitorEvent, FileMonitorFlags},
+    glib::{self, clone},
+    prelude::{
+        CheckButtonExt, EditableExt, FileExt, FileMonitorExt, GtkWindowExt, ObjectExt, WidgetExt,
+    },
+    ApplicationWindow, CheckButton, DropDown, Entry, Label, Revealer, SpinButton,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{AnimationFormat, AnimationSettings},
+    burst::BurstSettings,
+    copy_path::PathFormat,
+    inspect::COLOR_FORMAT_NAMES,
+    preferences::{BackendEntries, EditorDefaults},
+    recording::VideoFormat,
+    tools::{self, Tools},
+    ShotType,
+};
+
+/// How long to wait for more changes before writing the file.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+const SHOT_NAMES: [(&str, ShotType); 11] = [
+    ("fullscreen", ShotType::Fullscreen),
+    ("selection", ShotType::Selection),
+    ("pick-color", ShotType::PickColor),
+    ("text", ShotType::Text),
+    ("code", ShotType::Code),
+    ("record-screen", ShotType::Recording { selection: false }),
+    ("record-selection", ShotType::Recording { selection: true }),
+    ("animation", ShotType::Animation),
+    ("burst-screen", ShotType::Burst { selection: false }),
+    ("burst-selection", ShotType::Burst { selection: true }),
+    ("scrolling", ShotType::Scrolling),
+];
+
+/// Everything in `$XDG_CONFIG_HOME/shots/config.toml`, missing entries take their defaults.
+///
+/// The optional entries are left out while the GSettings schema is installed, which keeps them
+/// instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) delay: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cursor: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) freeze: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) copy_path_after_save: Option<bool>,
+    /// Empty for the pictures directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) save_directory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) path_format: Option<String>,
+    pub(crate) last_shot: String,
+    pub(crate) color_format: String,
+    pub(crate) text_language: String,
+    pub(crate) window: WindowConfig,
+    pub(crate) recording: RecordingConfig,
+    pub(crate) animation: AnimationConfig,
+    pub(crate) burst: BurstConfig,
+    pub(crate) editor: EditorConfig,
+    pub(crate) tools: Tools,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct WindowConfig {
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RecordingConfig {
+    pub(crate) format: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct AnimationConfig {
+    pub(crate) fps: u32,
+    pub(crate) seconds: u32,
+    pub(crate) format: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BurstConfig {
+    pub(crate) count: u32,
+    pub(crate) interval: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct EditorConfig {
+    /// Any colour GTK can parse, like `#ff0000` or `rgb(255,0,0)`.
+    pub(crate) color: String,
+    pub(crate) stroke_width: u32,
+    pub(crate) magnification: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            delay: Some(0),
+            cursor: Some(false),
+            freeze: Some(true),
+            copy_path_after_save: Some(false),
+            save_directory: Some(String::new()),
+            path_format: Some(PathFormat::NAMES[0].to_string()),
+            last_shot: "selection".to_string(),
+            color_format: COLOR_FORMAT_NAMES[0].to_string(),
+            text_language: "eng".to_string(),
+            window: WindowConfig::default(),
+            recording: RecordingConfig::default(),
+            animation: AnimationConfig::default(),
+            burst: BurstConfig::default(),
+            editor: EditorConfig::default(),
+            tools: Tools::default(),
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            width: 850,
+            height: 550,
+        }
+    }
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        RecordingConfig {
+            format: VideoFormat::NAMES[0].to_string(),
+        }
+    }
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        AnimationConfig {
+            fps: 10,
+            seconds: 5,
+            format: AnimationFormat::NAMES[0].to_string(),
+        }
+    }
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        EditorConfig {
+            color: RGBA::RED.to_str().to_string(),
+            stroke_width: 4,
+            magnification: 3.0,
+        }
+    }
+}
+
+impl Default for BurstConfig {
+    fn default() -> Self {
+        BurstConfig {
+            count: 10,
+            interval: 2.0,
+        }
+    }
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(
+    name: &str,
+    value: T,
+    min: T,
+    max: T,
+) -> Result<()> {
+    if value < min || value > max {
+        Err(anyhow!(
+            "{name} must be between {min} and {max}, not {value}"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Position of `value` in the names shown by a drop down, ignoring case.
+fn name_index(name: &str, names: &[&str], value: &str) -> Result<u32> {
+    names
+        .iter()
+        .position(|candidate| candidate.eq_ignore_ascii_case(value))
+        .map(|index| index as u32)
+        .ok_or_else(|| anyhow!("{name} must be one of {}, not {value:?}", names.join(", ")))
+}
+
+pub(crate) fn shot_type(name: &str) -> Result<ShotType> {
+    SHOT_NAMES
+        .iter()
+        .find(|(candidate, _)| *candidate == name)
+        .map(|(_, shot)| *shot)
+        .ok_or_else(|| {
+            let names: Vec<&str> = SHOT_NAMES.iter().map(|(name, _)| *name).collect();
+            anyhow!(
+                "last-shot must be one of {}, not {name:?}",
+                names.join(", ")
+            )
+        })
+}
+
+fn shot_name(shot: ShotType) -> &'static str {
+    SHOT_NAMES
+        .iter()
+        .find(|(_, candidate)| *candidate == shot)
+        .map_or("selection", |(name, _)| *name)
+}
+
+impl Config {
+    pub(crate) fn path() -> PathBuf {
+        glib::user_config_dir().join("shots").join("config.toml")
+    }
+
+    /// Reads the file, `None` when there is none yet.
+    pub(crate) fn load() -> Result<Option<Self>> {
+        let path = Config::path();
+        let text = match read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        Config::parse(&text)
+            .map(Some)
+            .with_context(|| format!("loading {}", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let config: Config = toml::from_str(text).context("parsing configuration")?;
+        config.validate().context("validating configuration")?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(delay) = self.delay {
+            check_range("delay", delay, 0, 255)?;
+        }
+        if let Some(directory) = &self.save_directory {
+            if !directory.is_empty() && !Path::new(directory).is_absolute() {
+                return Err(anyhow!(
+                    "save-directory must be an absolute path, not {directory:?}"
+                ));
+            }
+        }
+        if let Some(format) = &self.path_format {
+            name_index("path-format", &PathFormat::NAMES, format)?;
+        }
+        shot_type(&self.last_shot)?;
+        name_index("color-format", &COLOR_FORMAT_NAMES, &self.color_format)?;
+        check_range("window.width", self.window.width, 1, 16384)?;
+        check_range("window.height", self.window.height, 1, 16384)?;
+        name_index(
+            "recording.format",
+            &VideoFormat::NAMES,
+            &self.recording.format,
+        )?;
+        check_range("animation.fps", self.animation.fps, 1, 30)?;
+        check_range("animation.seconds", self.animation.seconds, 1, 30)?;
+        name_index(
+            "animation.format",
+            &AnimationFormat::NAMES,
+            &self.animation.format,
+        )?;
+        check_range("burst.count", self.burst.count, 2, 999)?;
+        check_range("burst.interval", self.burst.interval, 0.5, 3600.0)?;
+        RGBA::parse(self.editor.color.as_str())
+            .map_err(|_| anyhow!("editor.color must be a colour, not {:?}", self.editor.color))?;
+        check_range("editor.stroke-width", self.editor.stroke_width, 1, 64)?;
+        check_range("editor.magnification", self.editor.magnification, 1.5, 16.0)?;
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Config::path();
+        if let Some(directory) = path.parent() {
+            create_dir_all(directory).context("creating configuration directory")?;
+        }
+        let text = toml::to_string_pretty(self).context("serializing configuration")?;
+        write(&path, text).with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+/// The widgets and state whose values are kept in the file.
+#[derive(Clone)]
+pub(crate) struct ConfigWidgets {
+    pub(crate) window: ApplicationWindow,
+    pub(crate) last_shot: Rc<Cell<ShotType>>,
+    pub(crate) delay_button: SpinButton,
+    pub(crate) cursor_check: CheckButton,
+    pub(crate) freeze_check: CheckButton,
+    pub(crate) copy_after_save_check: CheckButton,
+    pub(crate) save_directory: Entry,
+    pub(crate) path_format_drop: DropDown,
+    pub(crate) color_format_drop: DropDown,
+    pub(crate) text_language: Entry,
+    pub(crate) record_format_drop: DropDown,
+    pub(crate) animation: AnimationSettings,
+    pub(crate) burst: BurstSettings,
+    pub(crate) editor: EditorDefaults,
+    pub(crate) backends: BackendEntries,
+    /// The capture and clipboard settings are bound to GSettings instead of kept in the file.
+    pub(crate) desktop: bool,
+}
+
+impl ConfigWidgets {
+    /// Only called with validated configurations, so every name is known.
+    fn apply(&self, config: &Config) {
+        self.window
+            .set_default_size(config.window.width, config.window.height);
+        if let Ok(shot) = shot_type(&config.last_shot) {
+            self.last_shot.set(shot);
+        }
+        if let Some(delay) = config.delay.filter(|_| !self.desktop) {
+            self.delay_button.set_value(delay.into());
+        }
+        if let Some(cursor) = config.cursor.filter(|_| !self.desktop) {
+            self.cursor_check.set_active(cursor);
+        }
+        if let Some(freeze) = config.freeze.filter(|_| !self.desktop) {
+            if self.freeze_check.is_sensitive() {
+                self.freeze_check.set_active(freeze);
+            }
+        }
+        if let Some(copy) = config.copy_path_after_save.filter(|_| !self.desktop) {
+            self.copy_after_save_check.set_active(copy);
+        }
+        if let Some(directory) = config.save_directory.as_ref().filter(|_| !self.desktop) {
+            self.save_directory.set_text(directory);
+        }
+        if let Some(format) = config.path_format.as_ref().filter(|_| !self.desktop) {
+            if let Ok(index) = name_index("path-format", &PathFormat::NAMES, format) {
+                self.path_format_drop.set_selected(index);
+            }
+        }
+        if let Ok(index) = name_index("color-format", &COLOR_FORMAT_NAMES, &config.color_format) {
+            self.color_format_drop.set_selected(index);
+        }
+        self.text_language.set_text(&config.text_language);
+        if let Ok(index) = name_index(
+            "recording.format",
+            &VideoFormat::NAMES,
+            &config.recording.format,
+        ) {
+            self.record_format_drop.set_selected(index);
+        }
+        self.animation.fps.set_value(config.animation.fps.into());
+        self.animation
+            .seconds
+            .set_value(config.animation.seconds.into());
+        if let Ok(index) = name_index(
+            "animation.format",
+            &AnimationFormat::NAMES,
+            &config.animation.format,
+        ) {
+            self.animation.format_drop.set_selected(index);
+        }
+        self.burst.count.set_value(config.burst.count.into());
+        self.burst.interval.set_value(config.burst.interval);
+        if let Ok(color) = RGBA::parse(config.editor.color.as_str()) {
+            self.editor.color.set_rgba(&color);
+        }
+        self.editor
+            .stroke
+            .set_value(config.editor.stroke_width.into());
+        self.editor
+            .magnification
+            .set_value(config.editor.magnification);
+        self.backends.set(&config.tools);
+    }
+
+    /// The current values, entries without a widget are kept from `base`.
+    fn read(&self, base: &Config) -> Config {
+        let selected = |drop: &DropDown, names: &[&str]| {
+            names
+                .get(drop.selected() as usize)
+                .unwrap_or(&names[0])
+                .to_string()
+        };
+        let file = !self.desktop;
+        Config {
+            delay: file.then(|| self.delay_button.value() as u32),
+            cursor: file.then(|| self.cursor_check.is_active()),
+            freeze: if !file {
+                None
+            } else if self.freeze_check.is_sensitive() {
+                Some(self.freeze_check.is_active())
+            } else {
+                // unchecked because wayfreeze is missing, not by the user
+                base.freeze
+            },
+            copy_path_after_save: file.then(|| self.copy_after_save_check.is_active()),
+            save_directory: file.then(|| self.save_directory.text().to_string()),
+            path_format: file.then(|| selected(&self.path_format_drop, &PathFormat::NAMES)),
+            last_shot: shot_name(self.last_shot.get()).to_string(),
+            color_format: selected(&self.color_format_drop, &COLOR_FORMAT_NAMES),
+            text_language: self.text_language.text().to_string(),
+            window: WindowConfig {
+                width: self.window.default_width(),
+                height: self.window.default_height(),
+            },
+            recording: RecordingConfig {
+                format: selected(&self.record_format_drop, &VideoFormat::NAMES),
+            },
+            animation: AnimationConfig {
+                fps: self.animation.fps.value() as u32,
+                seconds: self.animation.seconds.value() as u32,
+                format: selected(&self.animation.format_drop, &AnimationFormat::NAMES),
+            },
+            burst: BurstConfig {
+                count: self.burst.count.value() as u32,
+                interval: self.burst.interval.value(),
+            },
+            editor: EditorConfig {
+                color: self.editor.color.rgba().to_str().to_string(),
+                stroke_width: self.editor.stroke.value() as u32,
+                magnification: self.editor.magnification.value(),
+            },
+            tools: self.backends.tools(),
+        }
+    }
+
+    fn connect_changed(&self, changed: impl Fn() + Clone + 'static) {
+        for spin in [
+            &self.delay_button,
+            &self.animation.fps,
+            &self.animation.seconds,
+            &self.burst.count,
+            &self.burst.interval,
+            &self.editor.stroke,
+            &self.editor.magnification,
+        ] {
+            let changed = changed.clone();
+            spin.connect_value_changed(move |_| changed());
+        }
+        for check in [
+            &self.cursor_check,
+            &self.freeze_check,
+            &self.copy_after_save_check,
+        ] {
+            let changed = changed.clone();
+            check.connect_toggled(move |_| changed());
+        }
+        for drop in [
+            &self.path_format_drop,
+            &self.color_format_drop,
+            &self.record_format_drop,
+            &self.animation.format_drop,
+        ] {
+            let changed = changed.clone();
+            drop.connect_selected_notify(move |_| changed());
+        }
+        for entry in [&self.text_language, &self.save_directory] {
+            let changed = changed.clone();
+            entry.connect_changed(move |_| changed());
+        }
+        self.backends.connect_committed(changed.clone());
+        let color_changed = changed.clone();
+        self.editor
+            .color
+            .connect_rgba_notify(move |_| color_changed());
+        // the last shot is set around hiding and showing the window for a capture
+        for property in ["default-width", "default-height", "visible"] {
+            let changed = changed.clone();
+            self.window
+                .connect_notify_local(Some(property), move |_, _| changed());
+        }
+    }
+}
+
+/// Applies a configuration and the tool settings it contains.
+fn use_config(config: &Config, widgets: &ConfigWidgets) -> Result<()> {
+    widgets.apply(config);
+    tools::set(config.tools.clone().with_env()?);
+    Ok(())
+}
+
+/// Loads the file at startup, writes it when settings change and reloads it when it is edited.
+///
+/// A file that fails to load is not overwritten until it loads again, so fixes aren't lost.
+pub(crate) fn connect_config(
+    widgets: &ConfigWidgets,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) {
+    let show_error = clone!(@strong error_revealer, @strong error_label => move |e: anyhow::Error| {
+        error_label.set_text(&format!("{e:?}"));
+        error_revealer.set_reveal_child(true);
+    });
+    let current = Rc::new(RefCell::new(Config::default()));
+    let writable = Rc::new(Cell::new(true));
+    let loading = Rc::new(Cell::new(false));
+
+    let load = clone!(
+        @strong widgets,
+        @strong current,
+        @strong writable,
+        @strong loading,
+        @strong show_error
+            => move || {
+                let result = Config::load().and_then(|config| {
+                    let Some(config) = config else {
+                        return Ok(());
+                    };
+                    if config != *current.borrow() {
+                        loading.set(true);
+                        let applied = use_config(&config, &widgets);
+                        loading.set(false);
+                        current.replace(config);
+                        applied?;
+                    }
+                    Ok(())
+                });
+                writable.set(result.is_ok());
+                if let Err(e) = result {
+                    show_error(e);
+                }
+            }
+    );
+    // environment overrides apply even without a file
+    match Tools::default().with_env() {
+        Ok(configured) => tools::set(configured),
+        Err(e) => show_error(e),
+    }
+    load();
+
+    let monitor = gio::File::for_path(Config::path())
+        .monitor_file(FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE);
+    let monitor: Rc<Option<FileMonitor>> = Rc::new(match monitor {
+        Ok(monitor) => {
+            monitor.connect_changed(move |_, _, _, event| {
+                if matches!(
+                    event,
+                    FileMonitorEvent::ChangesDoneHint
+                        | FileMonitorEvent::Created
+                        | FileMonitorEvent::Renamed
+                        | FileMonitorEvent::MovedIn
+                ) {
+                    load();
+                }
+            });
+            Some(monitor)
+        }
+        Err(e) => {
+            show_error(anyhow::Error::from(e).context("watching configuration file"));
+            None
+        }
+    });
+
+    let pending = Rc::new(Cell::new(false));
+    widgets.connect_changed(clone!(
+        @strong widgets,
+        @strong current,
+        @strong writable,
+        @strong loading,
+        @strong pending,
+        @strong show_error,
+        @strong monitor
+            => move || {
+                // the file stays watched for as long as the widgets it updates exist
+                let _ = &monitor;
+                if loading.get() || !writable.get() || pending.replace(true) {
+                    return;
+                }
+                glib::timeout_add_local_once(SAVE_DELAY, clone!(
+                    @strong widgets,
+                    @strong current,
+                    @strong pending,
+                    @strong show_error
+                        => move || {
+                            pending.set(false);
+                            let config = widgets.read(&current.borrow());
+                            if config == *current.borrow() {
+                                return;
+                            }
+                            if let Err(e) = config.save() {
+                                show_error(e);
+                                return;
+                            }
+                            let configured = config.tools.clone().with_env();
+                            current.replace(config);
+                            match configured {
+                                Ok(configured) => tools::set(configured),
+                                Err(e) => show_error(e),
+                            }
+                        }
+                ));
+            }
+    ));
+}
+
+/// Loads only the tool settings, for `shots --check`.
+pub(crate) fn load_tools() -> Result<Tools> {
+    Config::load()?
+        .map(|config| config.tools)
+        .unwrap_or_default()
+        .with_env()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        format!("{:#}", Config::parse(text).unwrap_err())
+    }
+
+    #[test]
+    fn empty_file_uses_defaults() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn defaults_round_trip() {
+        let text = toml::to_string_pretty(&Config::default()).unwrap();
+        assert_eq!(Config::parse(&text).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn leaves_out_desktop_settings() {
+        let config = Config {
+            delay: None,
+            cursor: None,
+            freeze: None,
+            copy_path_after_save: None,
+            save_directory: None,
+            path_format: None,
+            ..Config::default()
+        };
+        let text = toml::to_string_pretty(&config).unwrap();
+        assert!(!text.contains("delay"));
+        assert!(!text.contains("cursor"));
+        assert_eq!(Config::parse(&text).unwrap().delay, Some(0));
+    }
+
+    #[test]
+    fn parses_entries() {
+        let config = Config::parse(
+            r#"
+            delay = 3
+            last-shot = "burst-screen"
+            color-format = "HSL()"
+
+            [animation]
+            fps = 15
+
+            [editor]
+            color = "rgb(0,128,255)"
+            stroke-width = 8
+
+            [tools.grim]
+            path = "/opt/grim/bin/grim"
+            args = ["-l", "3"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.delay, Some(3));
+        assert_eq!(
+            shot_type(&config.last_shot).unwrap(),
+            ShotType::Burst { selection: false }
+        );
+        assert_eq!(config.animation.fps, 15);
+        assert_eq!(config.animation.seconds, 5);
+        assert_eq!(config.editor.stroke_width, 8);
+        assert_eq!(
+            config.tools.grim.path,
+            Some(PathBuf::from("/opt/grim/bin/grim"))
+        );
+        assert_eq!(config.tools.grim.args, ["-l", "3"]);
+        assert_eq!(config.tools.slurp, Default::default());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        assert!(error("delay = 300").contains("delay must be between 0 and 255"));
+        assert!(error(r#"save-directory = "Pictures""#).contains("absolute path"));
+        assert!(error("[animation]\nfps = 0").contains("animation.fps"));
+        assert!(error("[burst]\ninterval = 0.1").contains("burst.interval"));
+        assert!(error("[editor]\nmagnification = 20.0").contains("editor.magnification"));
+    }
+
+    #[test]
+    fn rejects_unknown_values() {
+        assert!(error(r#"last-shot = "everything""#).contains("last-shot must be one of"));
+        assert!(error(r#"color-format = "cmyk""#).contains("color-format must be one of"));
+        assert!(error(r#"path-format = "url""#).contains("path-format must be one of"));
+        assert!(error("[recording]\nformat = \"avi\"").contains("recording.format"));
+        assert!(
+            error("[editor]\ncolor = \"not a colour\"").contains("editor.color must be a colour")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_types() {
+        assert!(error("dealy = 3").contains("parsing configuration"));
+        assert!(error("[tools.grim]\nflags = []").contains("parsing configuration"));
+        assert!(error("cursor = \"yes\"").contains("parsing configuration"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gdk::{Clipboard, ContentProvider},
+    gio::File,
+    glib::{clone, Bytes, ToValue},
+    prelude::FileExt,
+    Button, DropDown, Label, Revealer,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PathFormat {
+    Path,
+    Uri,
+}
+
+impl PathFormat {
+    /// How the formats are named in the settings, in the order of the drop down.
+    pub(crate) const NAMES: [&'static str; 2] = ["path", "uri"];
+
+    pub(crate) fn from_selected(path_format: &DropDown) -> Self {
+        match path_format.selected() {
+            1 => PathFormat::Uri,
+            _ => PathFormat::Path,
+        }
+    }
+}
+
+pub(crate) fn copy_path(file: &File, format: PathFormat, clipboard: &Clipboard) -> Result<()> {
+    match format {
+        PathFormat::Path => {
+            let path = file
+                .path()
+                .ok_or_else(|| anyhow!("saved file has no local path"))?;
+            clipboard.set_text(
+                path.to_str()
+                    .context("saved file path is not valid unicode")?,
+            );
+            Ok(())
+        }
+        PathFormat::Uri => {
+            let uri = file.uri();
+            clipboard
+                .set_content(Some(&ContentProvider::new_union(&[
+                    ContentProvider::for_bytes(
+                        "text/uri-list",
+                        &Bytes::from_owned(format!("{uri}\r\n")),
+                    ),
+                    ContentProvider::for_value(&uri.to_value()),
+                ])))
+                .context("Saving File URI to Clipboard")
+        }
+    }
+}
+
+fn handler(
+    saved_file: &Rc<RefCell<Option<File>>>,
+    path_format: &DropDown,
+    clipboard: &Clipboard,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) {
+    if let Err(e) = Ref::filter_map(saved_file.borrow(), Option::as_ref)
+        .map_err(|_| anyhow!("No screenshot has been saved yet"))
+        .and_then(|file| copy_path(&file, PathFormat::from_selected(path_format), clipboard))
+    {
+        error_label.set_text(&format!("{e:?}"));
+        error_revealer.set_reveal_child(true);
+    }
+}
+
+pub fn get_handler(
+    saved_file: &Rc<RefCell<Option<File>>>,
+    path_format: &DropDown,
+    clipboard: &Clipboard,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong saved_file,
+        @strong path_format,
+        @strong clipboard,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| handler(&saved_file, &path_format, &clipboard, &error_revealer, &error_label)
+    )
+}
@@ -0,0 +1,310 @@
+use std::{
+    cell::{Cell, RefCell},
+    f64::consts::PI,
+    rc::Rc,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    cairo::FillRule,
+    glib::{self, clone},
+    prelude::{ButtonExt, DrawingAreaExtManual, GestureDragExt, GestureExt, TextureExt, WidgetExt},
+    Button, DrawingArea, DropDown, EventSequenceState, GestureDrag, Label, Picture, Revealer,
+    SpinButton,
+};
+
+use crate::{
+    annotate::{show_error, target_tools, Tool},
+    editor::{show_texture, Document, Operation, Point},
+};
+
+const HANDLE_RADIUS: f64 = 12.0;
+
+pub(crate) const ASPECT_NAMES: [&str; 6] = ["Free", "1:1", "4:3", "3:2", "16:9", "Original"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Selection {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Selection {
+    fn spanning(anchor: Point, point: Point) -> Self {
+        Selection {
+            x: anchor.0.min(point.0),
+            y: anchor.1.min(point.1),
+            width: (point.0 - anchor.0).abs(),
+            height: (point.1 - anchor.1).abs(),
+        }
+    }
+
+    fn corners(&self) -> [Point; 4] {
+        [
+            (self.x, self.y),
+            (self.x + self.width, self.y),
+            (self.x + self.width, self.y + self.height),
+            (self.x, self.y + self.height),
+        ]
+    }
+
+    /// Returns the corner opposite to the handle at `point`, if there is one.
+    /// Handles keep their on-screen size, so the radius is divided by the preview `scale`.
+    fn anchor_for_handle(&self, point: Point, scale: f64) -> Option<Point> {
+        let corners = self.corners();
+        corners
+            .iter()
+            .position(|corner| {
+                (corner.0 - point.0).hypot(corner.1 - point.1) <= HANDLE_RADIUS / scale
+            })
+            .map(|index| corners[(index + 2) % 4])
+    }
+}
+
+fn aspect_ratio(aspect_drop: &DropDown, image: &Rc<RefCell<Option<Document>>>) -> Option<f64> {
+    match aspect_drop.selected() {
+        1 => Some(1.0),
+        2 => Some(4.0 / 3.0),
+        3 => Some(3.0 / 2.0),
+        4 => Some(16.0 / 9.0),
+        5 => image
+            .borrow()
+            .as_ref()
+            .map(|document| document.texture().width() as f64 / document.texture().height() as f64),
+        _ => None,
+    }
+}
+
+/// Moves `point` so the rectangle spanned with `anchor` has the requested aspect ratio.
+fn constrain(anchor: Point, point: Point, ratio: Option<f64>) -> Point {
+    let Some(ratio) = ratio else {
+        return point;
+    };
+    let (dx, dy) = (point.0 - anchor.0, point.1 - anchor.1);
+    if dx.abs() / ratio > dy.abs() {
+        (point.0, anchor.1 + dy.signum() * dx.abs() / ratio)
+    } else {
+        (anchor.0 + dx.signum() * dy.abs() * ratio, point.1)
+    }
+}
+
+fn apply(
+    selection: Selection,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+) -> Result<()> {
+    let mut document = image.borrow_mut();
+    let document = document
+        .as_mut()
+        .ok_or_else(|| anyhow!("No screenshot available to crop"))?;
+    let x0 = (selection.x.round() as i32).max(0);
+    let y0 = (selection.y.round() as i32).max(0);
+    let x1 = ((selection.x + selection.width).round() as i32).min(document.texture().width());
+    let y1 = ((selection.y + selection.height).round() as i32).min(document.texture().height());
+    if x1 <= x0 || y1 <= y0 {
+        return Err(anyhow!("Crop selection is outside of the screenshot"));
+    }
+    document
+        .push(Operation::Crop {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+        })
+        .context("applying crop")?;
+    show_texture(image_view, document.texture());
+    Ok(())
+}
+
+fn draw_selection(cr: &gtk4::cairo::Context, selection: Selection, width: f64, height: f64) {
+    let (pixel, _) = cr.device_to_user_distance(1.0, 0.0).unwrap_or((1.0, 0.0));
+    cr.set_fill_rule(FillRule::EvenOdd);
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.5);
+    cr.rectangle(0.0, 0.0, width, height);
+    cr.rectangle(selection.x, selection.y, selection.width, selection.height);
+    let _ = cr.fill();
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.9);
+    cr.set_line_width(pixel);
+    cr.set_dash(&[6.0 * pixel, 4.0 * pixel], 0.0);
+    cr.rectangle(selection.x, selection.y, selection.width, selection.height);
+    let _ = cr.stroke();
+    cr.set_dash(&[], 0.0);
+    for corner in selection.corners() {
+        cr.arc(
+            corner.0,
+            corner.1,
+            HANDLE_RADIUS / 2.0 * pixel,
+            0.0,
+            2.0 * PI,
+        );
+        let _ = cr.fill();
+    }
+}
+
+pub(crate) fn connect_crop(
+    layer: &DrawingArea,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    tool_drop: &DropDown,
+    crop_revealer: &Revealer,
+    aspect_drop: &DropDown,
+    x_button: &SpinButton,
+    y_button: &SpinButton,
+    width_button: &SpinButton,
+    height_button: &SpinButton,
+    apply_button: &Button,
+    scale: &Rc<Cell<f64>>,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) {
+    let selection: Rc<Cell<Option<Selection>>> = Rc::new(Cell::new(None));
+    let anchor: Rc<Cell<Point>> = Rc::new(Cell::new((0.0, 0.0)));
+    let updating = Rc::new(Cell::new(false));
+
+    target_tools(layer, tool_drop, |tool| tool == Tool::Crop);
+    crop_revealer.set_reveal_child(Tool::from_selected(tool_drop) == Tool::Crop);
+    tool_drop.connect_selected_notify(clone!(
+        @weak crop_revealer,
+        @weak layer,
+        @strong selection
+            => move |tool_drop| {
+                let cropping = Tool::from_selected(tool_drop) == Tool::Crop;
+                crop_revealer.set_reveal_child(cropping);
+                if !cropping {
+                    selection.set(None);
+                    layer.queue_draw();
+                }
+            }
+    ));
+
+    layer.set_draw_func(
+        clone!(@strong selection, @strong scale => move |_, cr, width, height| {
+            if let Some(selection) = selection.get() {
+                let scale = scale.get();
+                cr.scale(scale, scale);
+                draw_selection(cr, selection, width as f64 / scale, height as f64 / scale);
+            }
+        }),
+    );
+
+    let show_selection = Rc::new(clone!(
+        @weak layer,
+        @strong selection,
+        @strong updating,
+        @weak x_button,
+        @weak y_button,
+        @weak width_button,
+        @weak height_button
+            => move |new_selection: Option<Selection>| {
+                selection.set(new_selection);
+                if let Some(new_selection) = new_selection {
+                    updating.set(true);
+                    x_button.set_value(new_selection.x.round());
+                    y_button.set_value(new_selection.y.round());
+                    width_button.set_value(new_selection.width.round());
+                    height_button.set_value(new_selection.height.round());
+                    updating.set(false);
+                }
+                layer.queue_draw();
+            }
+    ));
+
+    let spin_changed = Rc::new(clone!(
+        @weak layer,
+        @strong selection,
+        @strong updating,
+        @strong image,
+        @weak aspect_drop,
+        @weak x_button,
+        @weak y_button,
+        @weak width_button,
+        @weak height_button
+            => move |changed: &SpinButton| {
+                if updating.get() {
+                    return;
+                }
+                if let Some(ratio) = aspect_ratio(&aspect_drop, &image) {
+                    updating.set(true);
+                    if changed == &width_button {
+                        height_button.set_value((width_button.value() / ratio).round());
+                    } else if changed == &height_button {
+                        width_button.set_value((height_button.value() * ratio).round());
+                    }
+                    updating.set(false);
+                }
+                selection.set(Some(Selection {
+                    x: x_button.value(),
+                    y: y_button.value(),
+                    width: width_button.value(),
+                    height: height_button.value(),
+                }));
+                layer.queue_draw();
+            }
+    ));
+    for button in [x_button, y_button, width_button, height_button] {
+        button.connect_value_changed(
+            clone!(@strong spin_changed => move |button| spin_changed(button)),
+        );
+    }
+
+    let drag = GestureDrag::new();
+    drag.connect_drag_begin(clone!(
+        @strong selection,
+        @strong anchor,
+        @strong tool_drop,
+        @strong scale
+            => move |drag, x, y| {
+                if Tool::from_selected(&tool_drop) != Tool::Crop {
+                    drag.set_state(EventSequenceState::Denied);
+                    return;
+                }
+                let scale = scale.get();
+                let point = (x / scale, y / scale);
+                anchor.set(
+                    selection
+                        .get()
+                        .and_then(|selection| selection.anchor_for_handle(point, scale))
+                        .unwrap_or(point),
+                );
+            }
+    ));
+    drag.connect_drag_update(clone!(
+        @strong anchor,
+        @strong image,
+        @weak aspect_drop,
+        @strong show_selection,
+        @strong scale
+            => move |drag, x, y| {
+                if let Some((start_x, start_y)) = drag.start_point() {
+                    let scale = scale.get();
+                    let point = constrain(
+                        anchor.get(),
+                        ((start_x + x) / scale, (start_y + y) / scale),
+                        aspect_ratio(&aspect_drop, &image),
+                    );
+                    show_selection(Some(Selection::spanning(anchor.get(), point)));
+                }
+            }
+    ));
+    layer.add_controller(drag);
+
+    apply_button.connect_clicked(clone!(
+        @strong selection,
+        @strong image,
+        @weak image_view,
+        @strong show_selection,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                let result = selection
+                    .get()
+                    .ok_or_else(|| anyhow!("Select an area to crop first"))
+                    .and_then(|selection| apply(selection, &image, &image_view));
+                match result {
+                    Ok(()) => show_selection(None),
+                    Err(e) => show_error(e, &error_revealer, &error_label),
+                }
+            }
+    ));
+}
@@ -0,0 +1,270 @@
+use std::{ffi::OsString, path::PathBuf, process::Command, rc::Rc};
+
+use gtk4::{
+    gio,
+    glib::{self, clone, MainContext},
+    prelude::{ButtonExt, CheckButtonExt, WidgetExt},
+    Button, CheckButton, Label, Revealer,
+};
+
+use crate::tools::program;
+
+const SCREENCOPY: &str = "zwlr_screencopy_manager_v1";
+const LAYER_SHELL: &str = "zwlr_layer_shell_v1";
+
+/// The external programs Shots runs, with the package that usually provides them.
+const TOOLS: [(&str, &str); 6] = [
+    ("grim", "grim"),
+    ("slurp", "slurp"),
+    ("wayfreeze", "wayfreeze"),
+    ("wf-recorder", "wf-recorder"),
+    ("tesseract", "tesseract"),
+    ("zbarimg", "zbar"),
+];
+const PROTOCOLS: [&str; 2] = [SCREENCOPY, LAYER_SHELL];
+
+/// Everything a button needs to work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Fullscreen,
+    Selection,
+    Freeze,
+    ExtractText,
+    CaptureText,
+    ScanImage,
+    CaptureCode,
+    RecordScreen,
+    RecordSelection,
+}
+
+impl Mode {
+    const ALL: [Mode; 9] = [
+        Mode::Fullscreen,
+        Mode::Selection,
+        Mode::Freeze,
+        Mode::ExtractText,
+        Mode::CaptureText,
+        Mode::ScanImage,
+        Mode::CaptureCode,
+        Mode::RecordScreen,
+        Mode::RecordSelection,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Fullscreen => "Full screen capture and burst",
+            Mode::Selection => "Selection, colour, burst, scrolling and animation capture",
+            Mode::Freeze => "Freezing the screen",
+            Mode::ExtractText => "Text extraction",
+            Mode::CaptureText => "Text capture",
+            Mode::ScanImage => "Code scanning",
+            Mode::CaptureCode => "Code capture",
+            Mode::RecordScreen => "Screen recording",
+            Mode::RecordSelection => "Selection recording",
+        }
+    }
+
+    fn tools(self) -> &'static [&'static str] {
+        match self {
+            Mode::Fullscreen => &["grim"],
+            Mode::Selection => &["grim", "slurp"],
+            Mode::Freeze => &["wayfreeze"],
+            Mode::ExtractText => &["tesseract"],
+            Mode::CaptureText => &["grim", "slurp", "tesseract"],
+            Mode::ScanImage => &["zbarimg"],
+            Mode::CaptureCode => &["grim", "slurp", "zbarimg"],
+            Mode::RecordScreen => &["wf-recorder"],
+            Mode::RecordSelection => &["slurp", "wf-recorder"],
+        }
+    }
+
+    fn protocols(self) -> &'static [&'static str] {
+        match self {
+            Mode::ExtractText | Mode::ScanImage => &[],
+            Mode::Fullscreen | Mode::RecordScreen => &[SCREENCOPY],
+            _ => &[SCREENCOPY, LAYER_SHELL],
+        }
+    }
+}
+
+fn configured_programs() -> Vec<OsString> {
+    TOOLS.iter().map(|(tool, _)| program(tool)).collect()
+}
+
+/// Which tools are installed and which protocols the compositor offers.
+pub(crate) struct Report {
+    /// Each tool with the path it was found at.
+    tools: Vec<(&'static str, Option<PathBuf>)>,
+    /// `None` when wayland-info is not available to list the compositor's globals.
+    protocols: Option<Vec<String>>,
+}
+
+impl Report {
+    /// `programs` are the configured executables of `TOOLS`, in the same order.
+    fn probe(programs: Vec<OsString>) -> Self {
+        let tools = TOOLS
+            .iter()
+            .zip(programs)
+            .map(|((tool, _), program)| (*tool, glib::find_program_in_path(program)))
+            .collect();
+        let protocols = Command::new("wayland-info")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter_map(|line| line.split('\'').nth(1))
+                    .map(str::to_string)
+                    .collect()
+            });
+        Report { tools, protocols }
+    }
+
+    fn missing_protocols(&self, mode: Mode) -> Vec<&'static str> {
+        match &self.protocols {
+            Some(protocols) => mode
+                .protocols()
+                .iter()
+                .copied()
+                .filter(|protocol| !protocols.iter().any(|offered| offered == protocol))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Why the mode can't work, if it can't.
+    pub(crate) fn unavailable(&self, mode: Mode) -> Option<String> {
+        let mut reasons: Vec<String> = mode
+            .tools()
+            .iter()
+            .filter(|tool| {
+                self.tools
+                    .iter()
+                    .any(|(name, path)| name == *tool && path.is_none())
+            })
+            .map(|tool| format!("{tool} is not installed"))
+            .collect();
+        reasons.extend(
+            self.missing_protocols(mode)
+                .into_iter()
+                .map(|protocol| format!("the compositor does not support {protocol}")),
+        );
+        if reasons.is_empty() {
+            None
+        } else {
+            Some(reasons.join(", "))
+        }
+    }
+
+    pub(crate) fn is_ok(&self) -> bool {
+        Mode::ALL
+            .iter()
+            .all(|mode| self.unavailable(*mode).is_none())
+    }
+
+    pub(crate) fn text(&self) -> String {
+        let mut text = String::from("Tools:\n");
+        for ((tool, package), (_, path)) in TOOLS.iter().zip(&self.tools) {
+            match path {
+                Some(path) => text.push_str(&format!("  {tool}: {}\n", path.display())),
+                None => text.push_str(&format!("  {tool}: missing, install {package}\n")),
+            }
+        }
+        text.push_str("Wayland protocols:\n");
+        match &self.protocols {
+            Some(protocols) => {
+                for protocol in PROTOCOLS {
+                    let state = if protocols.iter().any(|offered| offered == protocol) {
+                        "supported"
+                    } else {
+                        "not supported by the compositor"
+                    };
+                    text.push_str(&format!("  {protocol}: {state}\n"));
+                }
+            }
+            None => text.push_str("  unknown, install wayland-utils to check\n"),
+        }
+        text.push_str("Capture modes:\n");
+        for mode in Mode::ALL {
+            match self.unavailable(mode) {
+                Some(reason) => {
+                    text.push_str(&format!("  {}: unavailable, {reason}\n", mode.name()))
+                }
+                None => text.push_str(&format!("  {}: available\n", mode.name())),
+            }
+        }
+        text
+    }
+
+    /// Disables the buttons of unavailable modes and explains why in their tooltip.
+    pub(crate) fn apply(&self, buttons: &[(Mode, Button)], freeze_check: &CheckButton) {
+        for (mode, button) in buttons {
+            let reason = self.unavailable(*mode);
+            button.set_sensitive(reason.is_none());
+            button.set_tooltip_text(reason.as_deref());
+        }
+        // insensitive first, so the stored settings keep the user's choice
+        let reason = self.unavailable(Mode::Freeze);
+        freeze_check.set_sensitive(reason.is_none());
+        freeze_check.set_tooltip_text(reason.as_deref());
+        if reason.is_some() {
+            freeze_check.set_active(false);
+        }
+    }
+}
+
+/// Shows the report and greys out what can't work.
+#[derive(Clone)]
+pub(crate) struct DoctorPanel {
+    pub(crate) revealer: Revealer,
+    pub(crate) label: Label,
+    pub(crate) buttons: Rc<Vec<(Mode, Button)>>,
+    pub(crate) freeze_check: CheckButton,
+}
+
+impl DoctorPanel {
+    /// The report is only revealed on its own when something is missing.
+    async fn check(&self, always_show: bool) {
+        let programs = configured_programs();
+        let Ok(report) = gio::spawn_blocking(move || Report::probe(programs)).await else {
+            return;
+        };
+        report.apply(&self.buttons, &self.freeze_check);
+        self.label.set_text(report.text().trim_end());
+        if always_show || !report.is_ok() {
+            self.revealer.set_reveal_child(true);
+        }
+    }
+}
+
+/// Checks once at startup and again whenever `check_button` is clicked.
+pub(crate) fn connect_doctor(
+    main_context: &MainContext,
+    panel: &DoctorPanel,
+    check_button: &Button,
+) {
+    main_context.spawn_local(clone!(@strong panel => async move {
+        panel.check(false).await
+    }));
+    check_button.connect_clicked(clone!(
+        @strong main_context,
+        @strong panel
+            => move |_| {
+                main_context.spawn_local(clone!(@strong panel => async move {
+                    panel.check(true).await
+                }));
+            }
+    ));
+}
+
+/// Prints the report for `shots --check` and returns the exit code.
+pub(crate) fn print_report() -> i32 {
+    let report = Report::probe(configured_programs());
+    print!("{}", report.text());
+    if report.is_ok() {
+        0
+    } else {
+        1
+    }
+}
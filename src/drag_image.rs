@@ -0,0 +1,53 @@
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+};
+
+use anyhow::{anyhow, Result};
+use gtk4::{
+    gdk::{ContentProvider, DragAction, FileList},
+    gio::File,
+    glib::{clone, ToValue},
+    traits::WidgetExt,
+    DragSource, Label, Picture, Revealer,
+};
+
+use crate::editor::Document;
+
+fn drag_content(image: &Rc<RefCell<Option<Document>>>) -> Result<ContentProvider> {
+    let document = Ref::filter_map(image.borrow(), Option::as_ref)
+        .map_err(|_| anyhow!("No screenshot available to drag"))?;
+    let bytes = document.bytes();
+    // offered as a file reference too, the file is removed when the screenshot is replaced
+    let file = File::for_path(document.temp_file()?);
+    Ok(ContentProvider::new_union(&[
+        ContentProvider::for_bytes("image/png", &bytes),
+        ContentProvider::for_value(&FileList::from_array(&[file]).to_value()),
+        ContentProvider::for_value(&document.texture().to_value()),
+    ]))
+}
+
+pub(crate) fn connect_drag(
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) {
+    let drag_source = DragSource::builder().actions(DragAction::COPY).build();
+    drag_source.connect_prepare(clone!(
+        @strong image,
+        @strong error_revealer,
+        @strong error_label
+            => move |_, _, _| {
+                match drag_content(&image) {
+                    Ok(content) => Some(content),
+                    Err(e) => {
+                        error_label.set_text(&format!("{e:?}"));
+                        error_revealer.set_reveal_child(true);
+                        None
+                    }
+                }
+            }
+    ));
+    image_view.add_controller(drag_source);
+}
@@ -0,0 +1,653 @@
+use std::{cell::OnceCell, f64::consts::PI, fs::write, mem::replace, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use gtk4::{
+    cairo::{Context, FillRule, Filter, Format, ImageSurface, LineCap, LineJoin},
+    gdk::{MemoryFormat, MemoryTexture, Texture, RGBA},
+    glib::Bytes,
+    prelude::{TextureExt, TextureExtManual},
+    Picture,
+};
+
+use crate::{
+    auto_save::TempFile,
+    redact::{redact, Redaction},
+};
+
+pub(crate) type Point = (f64, f64);
+
+#[cfg(target_endian = "little")]
+const SURFACE_FORMAT: MemoryFormat = MemoryFormat::B8g8r8a8Premultiplied;
+#[cfg(target_endian = "big")]
+const SURFACE_FORMAT: MemoryFormat = MemoryFormat::A8r8g8b8Premultiplied;
+
+const TEXT_SCALE: f64 = 6.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Stroke {
+    pub color: RGBA,
+    pub width: f64,
+}
+
+impl Stroke {
+    fn apply(&self, cr: &Context) {
+        cr.set_source_rgba(
+            self.color.red() as f64,
+            self.color.green() as f64,
+            self.color.blue() as f64,
+            self.color.alpha() as f64,
+        );
+        cr.set_line_width(self.width);
+        cr.set_line_cap(LineCap::Round);
+        cr.set_line_join(LineJoin::Round);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Operation {
+    Arrow {
+        start: Point,
+        end: Point,
+        stroke: Stroke,
+    },
+    Line {
+        start: Point,
+        end: Point,
+        stroke: Stroke,
+    },
+    Rectangle {
+        start: Point,
+        end: Point,
+        stroke: Stroke,
+    },
+    Ellipse {
+        start: Point,
+        end: Point,
+        stroke: Stroke,
+    },
+    Pen {
+        points: Vec<Point>,
+        stroke: Stroke,
+    },
+    Text {
+        position: Point,
+        text: String,
+        stroke: Stroke,
+    },
+    Redact {
+        start: Point,
+        end: Point,
+        kind: Redaction,
+        size: usize,
+    },
+    Step {
+        center: Point,
+        number: u32,
+        stroke: Stroke,
+    },
+    Magnify {
+        start: Point,
+        end: Point,
+        destination: Point,
+        factor: f64,
+        stroke: Stroke,
+    },
+    Crop {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    Resize {
+        width: i32,
+        height: i32,
+    },
+}
+
+impl Operation {
+    /// Moves the point that is being dragged while the operation is created.
+    pub(crate) fn drag_to(&mut self, point: Point) {
+        match self {
+            Operation::Arrow { end, .. }
+            | Operation::Line { end, .. }
+            | Operation::Rectangle { end, .. }
+            | Operation::Ellipse { end, .. }
+            | Operation::Redact { end, .. } => *end = point,
+            Operation::Pen { points, .. } => points.push(point),
+            Operation::Text { position, .. }
+            | Operation::Step {
+                center: position, ..
+            } => *position = point,
+            Operation::Magnify { destination, .. } => *destination = point,
+            Operation::Crop { .. } | Operation::Resize { .. } => {}
+        }
+    }
+
+    pub(crate) fn draw(&self, cr: &Context) -> Result<()> {
+        match self {
+            Operation::Arrow { start, end, stroke } => {
+                stroke.apply(cr);
+                cr.move_to(start.0, start.1);
+                cr.line_to(end.0, end.1);
+                let angle = (end.1 - start.1).atan2(end.0 - start.0);
+                let head = (stroke.width * 4.0).max(12.0);
+                for side in [-1.0, 1.0] {
+                    let wing = angle + PI + side * PI / 6.0;
+                    cr.move_to(end.0, end.1);
+                    cr.line_to(end.0 + head * wing.cos(), end.1 + head * wing.sin());
+                }
+                cr.stroke()?;
+            }
+            Operation::Line { start, end, stroke } => {
+                stroke.apply(cr);
+                cr.move_to(start.0, start.1);
+                cr.line_to(end.0, end.1);
+                cr.stroke()?;
+            }
+            Operation::Rectangle { start, end, stroke } => {
+                stroke.apply(cr);
+                cr.rectangle(
+                    start.0.min(end.0),
+                    start.1.min(end.1),
+                    (end.0 - start.0).abs(),
+                    (end.1 - start.1).abs(),
+                );
+                cr.stroke()?;
+            }
+            Operation::Ellipse { start, end, stroke } => {
+                let radius_x = (end.0 - start.0).abs() / 2.0;
+                let radius_y = (end.1 - start.1).abs() / 2.0;
+                if radius_x > 0.0 && radius_y > 0.0 {
+                    stroke.apply(cr);
+                    cr.save()?;
+                    cr.translate((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+                    cr.scale(radius_x, radius_y);
+                    cr.arc(0.0, 0.0, 1.0, 0.0, 2.0 * PI);
+                    cr.restore()?;
+                    cr.stroke()?;
+                }
+            }
+            Operation::Pen { points, stroke } => {
+                stroke.apply(cr);
+                if let Some((first, rest)) = points.split_first() {
+                    cr.move_to(first.0, first.1);
+                    if rest.is_empty() {
+                        cr.line_to(first.0, first.1);
+                    }
+                    for point in rest {
+                        cr.line_to(point.0, point.1);
+                    }
+                }
+                cr.stroke()?;
+            }
+            Operation::Text {
+                position,
+                text,
+                stroke,
+            } => {
+                stroke.apply(cr);
+                cr.select_font_face(
+                    "Sans",
+                    gtk4::cairo::FontSlant::Normal,
+                    gtk4::cairo::FontWeight::Bold,
+                );
+                cr.set_font_size(stroke.width * TEXT_SCALE);
+                cr.move_to(position.0, position.1);
+                cr.show_text(text)?;
+            }
+            Operation::Redact { start, end, .. } => {
+                cr.set_source_rgba(0.5, 0.5, 0.5, 0.8);
+                cr.set_line_width(1.0);
+                cr.set_dash(&[4.0, 4.0], 0.0);
+                cr.rectangle(
+                    start.0.min(end.0),
+                    start.1.min(end.1),
+                    (end.0 - start.0).abs(),
+                    (end.1 - start.1).abs(),
+                );
+                cr.stroke()?;
+                cr.set_dash(&[], 0.0);
+            }
+            Operation::Step {
+                center,
+                number,
+                stroke,
+            } => {
+                let radius = step_radius(stroke);
+                stroke.apply(cr);
+                cr.arc(center.0, center.1, radius, 0.0, 2.0 * PI);
+                cr.fill()?;
+                let label = number.to_string();
+                cr.set_source_rgb(1.0, 1.0, 1.0);
+                cr.select_font_face(
+                    "Sans",
+                    gtk4::cairo::FontSlant::Normal,
+                    gtk4::cairo::FontWeight::Bold,
+                );
+                cr.set_font_size(radius * 1.2);
+                let extents = cr.text_extents(&label)?;
+                cr.move_to(
+                    center.0 - extents.x_bearing() - extents.width() / 2.0,
+                    center.1 - extents.y_bearing() - extents.height() / 2.0,
+                );
+                cr.show_text(&label)?;
+            }
+            Operation::Magnify {
+                start,
+                end,
+                destination,
+                factor,
+                stroke,
+            } => {
+                let (source, inset) = magnifier_frames(*start, *end, *destination, *factor);
+                stroke.apply(cr);
+                cr.save()?;
+                let (x1, y1, x2, y2) = cr.clip_extents()?;
+                cr.set_fill_rule(FillRule::EvenOdd);
+                cr.rectangle(x1, y1, x2 - x1, y2 - y1);
+                cr.rectangle(source[0], source[1], source[2], source[3]);
+                cr.rectangle(inset[0], inset[1], inset[2], inset[3]);
+                cr.clip();
+                cr.move_to(source[0] + source[2] / 2.0, source[1] + source[3] / 2.0);
+                cr.line_to(inset[0] + inset[2] / 2.0, inset[1] + inset[3] / 2.0);
+                cr.stroke()?;
+                cr.restore()?;
+                cr.rectangle(source[0], source[1], source[2], source[3]);
+                cr.rectangle(inset[0], inset[1], inset[2], inset[3]);
+                cr.stroke()?;
+            }
+            Operation::Crop { .. } | Operation::Resize { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Applies the operation to the pixels of the image.
+    /// Redactions replace the underlying pixels, crops and resizes replace the whole image and
+    /// everything else is drawn on top.
+    fn apply(&self, surface: &mut ImageSurface) -> Result<()> {
+        match self {
+            Operation::Redact {
+                start,
+                end,
+                kind,
+                size,
+            } => redact(surface, *start, *end, *kind, *size).context("redacting region"),
+            Operation::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                *surface = crop(surface, *x, *y, *width, *height).context("cropping image")?;
+                Ok(())
+            }
+            Operation::Resize { width, height } => {
+                *surface = resize(surface, *width, *height).context("resizing image")?;
+                Ok(())
+            }
+            Operation::Magnify {
+                start,
+                end,
+                destination,
+                factor,
+                ..
+            } => {
+                magnify(surface, *start, *end, *destination, *factor)
+                    .context("magnifying region")?;
+                let cr = Context::new(&*surface).context("creating drawing context")?;
+                self.draw(&cr).context("drawing magnifier")
+            }
+            _ => {
+                let cr = Context::new(&*surface).context("creating drawing context")?;
+                self.draw(&cr).context("drawing annotation")
+            }
+        }
+    }
+}
+
+fn step_radius(stroke: &Stroke) -> f64 {
+    (stroke.width * 3.0).max(12.0)
+}
+
+/// Finds the topmost step callout at `point` in the image edited from a base image of `size`.
+/// Callouts keep the coordinates they were placed at, so the point is mapped back through
+/// the crops and resizes recorded after each of them.
+fn step_at(operations: &[Operation], mut size: (i32, i32), point: Point) -> Option<usize> {
+    let sizes: Vec<_> = operations
+        .iter()
+        .map(|operation| {
+            let before = size;
+            if let Operation::Crop { width, height, .. } | Operation::Resize { width, height } =
+                operation
+            {
+                size = (*width, *height);
+            }
+            before
+        })
+        .collect();
+    // maps the coordinates of an operation to those of the edited image
+    let mut scale = (1.0, 1.0);
+    let mut offset = (0.0, 0.0);
+    for (index, operation) in operations.iter().enumerate().rev() {
+        match operation {
+            Operation::Step { center, stroke, .. } => {
+                let x = (point.0 - offset.0) / scale.0;
+                let y = (point.1 - offset.1) / scale.1;
+                if (center.0 - x).hypot(center.1 - y) <= step_radius(stroke) {
+                    return Some(index);
+                }
+            }
+            Operation::Crop { x, y, .. } => {
+                offset.0 -= *x as f64 * scale.0;
+                offset.1 -= *y as f64 * scale.1;
+            }
+            Operation::Resize { width, height } => {
+                let (before_width, before_height) = sizes[index];
+                scale.0 *= *width as f64 / before_width as f64;
+                scale.1 *= *height as f64 / before_height as f64;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Removes the step callout at `index` and renumbers the remaining ones.
+fn remove_step(operations: &mut Vec<Operation>, index: usize) {
+    operations.remove(index);
+    let mut next = 1;
+    for operation in operations {
+        if let Operation::Step { number, .. } = operation {
+            *number = next;
+            next += 1;
+        }
+    }
+}
+
+/// Source region and inset as `[x, y, width, height]`, the inset is centered on `destination`.
+fn magnifier_frames(
+    start: Point,
+    end: Point,
+    destination: Point,
+    factor: f64,
+) -> ([f64; 4], [f64; 4]) {
+    let source = [
+        start.0.min(end.0).round(),
+        start.1.min(end.1).round(),
+        (end.0 - start.0).abs().round(),
+        (end.1 - start.1).abs().round(),
+    ];
+    let (width, height) = (source[2] * factor, source[3] * factor);
+    let inset = [
+        destination.0 - width / 2.0,
+        destination.1 - height / 2.0,
+        width,
+        height,
+    ];
+    (source, inset)
+}
+
+fn magnify(
+    surface: &ImageSurface,
+    start: Point,
+    end: Point,
+    destination: Point,
+    factor: f64,
+) -> Result<()> {
+    let (source, inset) = magnifier_frames(start, end, destination, factor);
+    if source[2] < 1.0 || source[3] < 1.0 {
+        return Ok(());
+    }
+    let region = crop(
+        surface,
+        source[0] as i32,
+        source[1] as i32,
+        source[2] as i32,
+        source[3] as i32,
+    )?;
+    let cr = Context::new(surface)?;
+    cr.translate(inset[0], inset[1]);
+    cr.scale(factor, factor);
+    cr.set_source_surface(&region, 0.0, 0.0)?;
+    cr.source().set_filter(Filter::Nearest);
+    cr.paint()?;
+    Ok(())
+}
+
+fn crop(surface: &ImageSurface, x: i32, y: i32, width: i32, height: i32) -> Result<ImageSurface> {
+    let cropped = ImageSurface::create(Format::ARgb32, width, height)?;
+    let cr = Context::new(&cropped)?;
+    cr.set_source_surface(surface, -x as f64, -y as f64)?;
+    cr.paint()?;
+    drop(cr);
+    Ok(cropped)
+}
+
+pub(crate) fn resize(surface: &ImageSurface, width: i32, height: i32) -> Result<ImageSurface> {
+    let resized = ImageSurface::create(Format::ARgb32, width, height)?;
+    let cr = Context::new(&resized)?;
+    cr.scale(
+        width as f64 / surface.width() as f64,
+        height as f64 / surface.height() as f64,
+    );
+    cr.set_source_surface(surface, 0.0, 0.0)?;
+    cr.source().set_filter(Filter::Best);
+    cr.paint()?;
+    drop(cr);
+    Ok(resized)
+}
+
+pub(crate) fn surface_from_texture(texture: &Texture) -> Result<ImageSurface> {
+    let mut surface = ImageSurface::create(Format::ARgb32, texture.width(), texture.height())
+        .context("creating image surface")?;
+    let stride = surface.stride() as usize;
+    texture.download(
+        &mut surface.data().context("accessing image surface")?,
+        stride,
+    );
+    Ok(surface)
+}
+
+pub(crate) fn texture_from_surface(surface: ImageSurface) -> Result<Texture> {
+    surface.flush();
+    let width = surface.width();
+    let height = surface.height();
+    let stride = surface.stride() as usize;
+    let data = surface.take_data().context("accessing image surface")?;
+    Ok(MemoryTexture::new(
+        width,
+        height,
+        SURFACE_FORMAT,
+        &Bytes::from_owned(data),
+        stride,
+    )
+    .into())
+}
+
+/// A captured screenshot together with the edits applied on top of it.
+pub(crate) struct Document {
+    original: Bytes,
+    base: Texture,
+    operations: Vec<Operation>,
+    undo_stack: Vec<Vec<Operation>>,
+    redo_stack: Vec<Vec<Operation>>,
+    texture: Texture,
+    bytes: OnceCell<Bytes>,
+    temp_file: OnceCell<TempFile>,
+}
+
+impl Document {
+    pub(crate) fn new(original: Bytes) -> Result<Self> {
+        let base = Texture::from_bytes(&original).context("loading screenshot image")?;
+        Ok(Document {
+            bytes: OnceCell::from(original.clone()),
+            original,
+            texture: base.clone(),
+            base,
+            operations: Vec::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            temp_file: OnceCell::new(),
+        })
+    }
+
+    pub(crate) fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// PNG encoded image with all edits applied.
+    pub(crate) fn bytes(&self) -> Bytes {
+        self.bytes
+            .get_or_init(|| self.texture.save_to_png_bytes())
+            .clone()
+    }
+
+    /// Writes the image with all edits applied to a temporary file that is kept
+    /// for the lifetime of the document, e.g. for dragging it out as a file.
+    pub(crate) fn temp_file(&self) -> Result<PathBuf> {
+        let temp_file = match self.temp_file.get() {
+            Some(temp_file) => temp_file,
+            None => {
+                let temp_file = TempFile::new("png")?;
+                self.temp_file.get_or_init(|| temp_file)
+            }
+        };
+        write(temp_file.path(), self.bytes()).context("writing temporary file")?;
+        Ok(temp_file.path().to_path_buf())
+    }
+
+    pub(crate) fn push(&mut self, operation: Operation) -> Result<()> {
+        self.record();
+        self.operations.push(operation);
+        self.render()
+    }
+
+    pub(crate) fn undo(&mut self) -> Result<()> {
+        if let Some(operations) = self.undo_stack.pop() {
+            self.redo_stack
+                .push(replace(&mut self.operations, operations));
+            self.render()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn redo(&mut self) -> Result<()> {
+        if let Some(operations) = self.redo_stack.pop() {
+            self.undo_stack
+                .push(replace(&mut self.operations, operations));
+            self.render()?;
+        }
+        Ok(())
+    }
+
+    /// Drops all edits, returning to the untouched capture. This can itself be undone.
+    pub(crate) fn revert(&mut self) -> Result<()> {
+        if !self.operations.is_empty() {
+            self.record();
+            self.operations.clear();
+            self.render()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn next_step(&self) -> u32 {
+        self.operations
+            .iter()
+            .filter(|operation| matches!(operation, Operation::Step { .. }))
+            .count() as u32
+            + 1
+    }
+
+    /// Removes the topmost step callout at `point` and renumbers the remaining ones.
+    /// Returns whether a callout was found.
+    pub(crate) fn remove_step_at(&mut self, point: Point) -> Result<bool> {
+        let size = (self.base.width(), self.base.height());
+        let Some(index) = step_at(&self.operations, size, point) else {
+            return Ok(false);
+        };
+        self.record();
+        remove_step(&mut self.operations, index);
+        self.render()?;
+        Ok(true)
+    }
+
+    fn record(&mut self) {
+        self.undo_stack.push(self.operations.clone());
+        self.redo_stack.clear();
+    }
+
+    fn render(&mut self) -> Result<()> {
+        if self.operations.is_empty() {
+            self.texture = self.base.clone();
+            self.bytes = OnceCell::from(self.original.clone());
+            return Ok(());
+        }
+        let mut surface = surface_from_texture(&self.base)?;
+        for operation in &self.operations {
+            operation.apply(&mut surface)?;
+        }
+        self.texture = texture_from_surface(surface)?;
+        self.bytes = OnceCell::new();
+        Ok(())
+    }
+}
+
+pub(crate) fn show_texture(image_view: &Picture, texture: &Texture) {
+    // the size of the preview is managed by the zoom, see `zoom::ZoomView`
+    image_view.set_paintable(Some(texture));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(center: Point, number: u32) -> Operation {
+        Operation::Step {
+            center,
+            number,
+            stroke: Stroke {
+                color: RGBA::RED,
+                width: 2.0,
+            },
+        }
+    }
+
+    #[test]
+    fn removes_step_placed_before_crop_and_resize() {
+        let mut operations = vec![
+            step((100.0, 100.0), 1),
+            Operation::Crop {
+                x: 50,
+                y: 40,
+                width: 200,
+                height: 150,
+            },
+            Operation::Resize {
+                width: 100,
+                height: 75,
+            },
+            step((80.0, 60.0), 2),
+        ];
+        let size = (400, 300);
+
+        // the first callout is now drawn at half size around (25, 30)
+        assert_eq!(step_at(&operations, size, (25.0, 30.0)), Some(0));
+        assert_eq!(step_at(&operations, size, (30.0, 30.0)), Some(0));
+        assert_eq!(step_at(&operations, size, (33.0, 30.0)), None);
+        // where the callout was placed before the edits
+        assert_eq!(step_at(&operations, size, (100.0, 100.0)), None);
+        assert_eq!(step_at(&operations, size, (85.0, 60.0)), Some(3));
+
+        remove_step(&mut operations, 0);
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[2], step((80.0, 60.0), 1));
+        assert_eq!(step_at(&operations, size, (25.0, 30.0)), None);
+    }
+
+    #[test]
+    fn finds_topmost_step() {
+        let operations = vec![step((10.0, 10.0), 1), step((14.0, 10.0), 2)];
+        assert_eq!(step_at(&operations, (100, 100), (12.0, 10.0)), Some(1));
+        assert_eq!(step_at(&operations, (100, 100), (50.0, 50.0)), None);
+    }
+}
@@ -0,0 +1,49 @@
+use std::ffi::{OsStr, OsString};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gio::{DataInputStream, Subprocess, SubprocessFlags},
+    glib::Priority,
+    prelude::DataInputStreamExtManual,
+};
+
+use crate::{command::spawn_context, tools::command_line, KillSubprocessGuard};
+
+/// Run by wayfreeze once the frozen image is on screen, its output tells us the freeze is up.
+const READY_COMMAND: &str = "echo frozen";
+
+/// Freezes the screen and waits until the frozen image is shown, so slurp draws on top of it.
+///
+/// The frozen image only contains the cursor when `cursor` is set. Captures taken while the
+/// returned guard is alive show the frozen image, it is removed when the guard is dropped.
+pub(crate) async fn freeze(cursor: bool) -> Result<KillSubprocessGuard> {
+    let mut argv = vec![
+        OsStr::new("wayfreeze"),
+        OsStr::new("--after-freeze-cmd"),
+        OsStr::new(READY_COMMAND),
+    ];
+    if !cursor {
+        argv.push(OsStr::new("--hide-cursor"));
+    }
+    let argv = command_line(&argv);
+    let argv: Vec<&OsStr> = argv.iter().map(OsString::as_os_str).collect();
+    let process = Subprocess::newv(&argv, SubprocessFlags::STDOUT_PIPE)
+        .with_context(|| spawn_context(argv[0]))?;
+    let guard = KillSubprocessGuard::new(process.clone());
+    let stdout = DataInputStream::new(&process.stdout_pipe().expect("stdout pipe"));
+    let line = stdout
+        .read_line_future(Priority::DEFAULT)
+        .await
+        .context("waiting for wayfreeze to freeze the screen")?;
+    if line.is_empty() {
+        process
+            .wait_future()
+            .await
+            .context("waiting for wayfreeze to exit")?;
+        return Err(anyhow!(
+            "wayfreeze exited with status {} before freezing the screen",
+            process.exit_status()
+        ));
+    }
+    Ok(guard)
+}
@@ -0,0 +1,92 @@
+use gtk4::{
+    gio::{Settings, SettingsSchemaSource},
+    glib::{self, clone, ToValue, ToVariant},
+    prelude::{SettingsExt, SettingsExtManual, WidgetExt},
+    CheckButton, DropDown, Entry, SpinButton,
+};
+
+use crate::{config::shot_type, copy_path::PathFormat, ShotType};
+
+const SCHEMA_ID: &str = "com.shots";
+
+/// The desktop settings, `None` unless the schema is installed.
+pub(crate) fn settings() -> Option<Settings> {
+    SettingsSchemaSource::default()?.lookup(SCHEMA_ID, true)?;
+    Some(Settings::new(SCHEMA_ID))
+}
+
+/// The mode an activation starts, `None` to repeat the last one.
+pub(crate) fn default_mode() -> Option<ShotType> {
+    shot_type(&settings()?.string("default-mode")).ok()
+}
+
+#[derive(Clone)]
+pub(crate) struct SettingsWidgets {
+    pub(crate) delay_button: SpinButton,
+    pub(crate) cursor_check: CheckButton,
+    pub(crate) freeze_check: CheckButton,
+    pub(crate) copy_after_save_check: CheckButton,
+    pub(crate) path_format_drop: DropDown,
+    pub(crate) save_directory: Entry,
+}
+
+/// Keeps the widgets and the schema's keys in sync, so they can be managed with dconf.
+/// The configuration file leaves these settings out while the schema is installed.
+pub(crate) fn bind_settings(widgets: &SettingsWidgets) {
+    let Some(settings) = settings() else {
+        return;
+    };
+    settings
+        .bind("delay", &widgets.delay_button, "value")
+        .mapping(|variant, _| {
+            variant
+                .get::<u32>()
+                .map(|delay| f64::from(delay).to_value())
+        })
+        .set_mapping(|value, _| {
+            value
+                .get::<f64>()
+                .ok()
+                .map(|delay| (delay as u32).to_variant())
+        })
+        .build();
+    settings
+        .bind("cursor", &widgets.cursor_check, "active")
+        .build();
+    let freeze_check = &widgets.freeze_check;
+    settings
+        .bind("freeze-screen", freeze_check, "active")
+        // unchecked because wayfreeze is missing, not by the user
+        .set_mapping(
+            clone!(@weak freeze_check => @default-return None, move |value, _| {
+                if freeze_check.is_sensitive() {
+                    value.get::<bool>().ok().map(|freeze| freeze.to_variant())
+                } else {
+                    None
+                }
+            }),
+        )
+        .build();
+    settings
+        .bind("save-directory", &widgets.save_directory, "text")
+        .build();
+    settings
+        .bind(
+            "copy-path-after-save",
+            &widgets.copy_after_save_check,
+            "active",
+        )
+        .build();
+    settings
+        .bind("path-format", &widgets.path_format_drop, "selected")
+        .mapping(|variant, _| {
+            let format = variant.str()?;
+            let index = PathFormat::NAMES.iter().position(|name| *name == format)?;
+            Some((index as u32).to_value())
+        })
+        .set_mapping(|value, _| {
+            let index = value.get::<u32>().ok()?;
+            Some(PathFormat::NAMES.get(index as usize)?.to_variant())
+        })
+        .build();
+}
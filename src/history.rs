@@ -0,0 +1,58 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::Result;
+use gtk4::{glib::clone, Button, Label, Picture, Revealer};
+
+use crate::editor::{show_texture, Document};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HistoryAction {
+    Undo,
+    Redo,
+    Revert,
+}
+
+fn run(
+    action: HistoryAction,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+) -> Result<()> {
+    if let Some(document) = image.borrow_mut().as_mut() {
+        match action {
+            HistoryAction::Undo => document.undo()?,
+            HistoryAction::Redo => document.redo()?,
+            HistoryAction::Revert => document.revert()?,
+        }
+        show_texture(image_view, document.texture());
+    }
+    Ok(())
+}
+
+pub(crate) fn handler(
+    action: HistoryAction,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) {
+    if let Err(e) = run(action, image, image_view) {
+        error_label.set_text(&format!("{e:?}"));
+        error_revealer.set_reveal_child(true);
+    }
+}
+
+pub(crate) fn get_handler(
+    action: HistoryAction,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong image,
+        @strong image_view,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| handler(action, &image, &image_view, &error_revealer, &error_label)
+    )
+}
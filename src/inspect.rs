@@ -0,0 +1,232 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    cairo::ImageSurface,
+    gdk::{Clipboard, Texture},
+    glib::{self, clone},
+    prelude::{DrawingAreaExtManual, WidgetExt},
+    DrawingArea, DropDown, EventControllerMotion, GestureClick, Label, Revealer,
+};
+
+use crate::{
+    annotate::{show_error, target_tools, Tool},
+    editor::{surface_from_texture, Document},
+};
+
+pub(crate) const COLOR_FORMAT_NAMES: [&str; 3] = ["Hex", "rgb()", "hsl()"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColorFormat {
+    Hex,
+    Rgb,
+    Hsl,
+}
+
+impl ColorFormat {
+    pub(crate) fn from_selected(format_drop: &DropDown) -> Self {
+        match format_drop.selected() {
+            1 => ColorFormat::Rgb,
+            2 => ColorFormat::Hsl,
+            _ => ColorFormat::Hex,
+        }
+    }
+
+    /// Formats a straight (not premultiplied) RGBA colour, alpha is only included when the
+    /// colour is not fully opaque.
+    pub(crate) fn format(self, [red, green, blue, alpha]: [u8; 4]) -> String {
+        let opaque = alpha == u8::MAX;
+        let alpha_fraction = alpha as f64 / 255.0;
+        match self {
+            ColorFormat::Hex if opaque => format!("#{red:02x}{green:02x}{blue:02x}"),
+            ColorFormat::Hex => format!("#{red:02x}{green:02x}{blue:02x}{alpha:02x}"),
+            ColorFormat::Rgb if opaque => format!("rgb({red}, {green}, {blue})"),
+            ColorFormat::Rgb => format!("rgba({red}, {green}, {blue}, {alpha_fraction:.2})"),
+            ColorFormat::Hsl => {
+                let (hue, saturation, lightness) = hsl(red, green, blue);
+                if opaque {
+                    format!("hsl({hue:.0}, {saturation:.0}%, {lightness:.0}%)")
+                } else {
+                    format!(
+                        "hsla({hue:.0}, {saturation:.0}%, {lightness:.0}%, {alpha_fraction:.2})"
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Returns hue in degrees and saturation and lightness in percent.
+fn hsl(red: u8, green: u8, blue: u8) -> (f64, f64, f64) {
+    let (r, g, b) = (
+        red as f64 / 255.0,
+        green as f64 / 255.0,
+        blue as f64 / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, lightness * 100.0);
+    }
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, saturation * 100.0, lightness * 100.0)
+}
+
+/// Converts a premultiplied cairo ARGB32 pixel, stored as native endian u32, to straight RGBA.
+pub(crate) fn straight_rgba(pixel: [u8; 4]) -> [u8; 4] {
+    let [alpha, red, green, blue] = u32::from_ne_bytes(pixel).to_be_bytes();
+    let unpremultiply = |channel: u8| match alpha {
+        0 => 0,
+        alpha => ((channel as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8,
+    };
+    [
+        unpremultiply(red),
+        unpremultiply(green),
+        unpremultiply(blue),
+        alpha,
+    ]
+}
+
+/// Reads the pixel at `x`, `y` from a cairo ARGB32 surface as straight RGBA.
+pub(crate) fn pixel_at(surface: &mut ImageSurface, x: i32, y: i32) -> Result<Option<[u8; 4]>> {
+    if x < 0 || y < 0 || x >= surface.width() || y >= surface.height() {
+        return Ok(None);
+    }
+    let stride = surface.stride() as usize;
+    let data = surface.data().context("accessing image surface")?;
+    let offset = y as usize * stride + x as usize * 4;
+    let pixel = data[offset..offset + 4]
+        .try_into()
+        .context("reading pixel")?;
+    Ok(Some(straight_rgba(pixel)))
+}
+
+fn describe(point: (i32, i32), color: [u8; 4], format: ColorFormat) -> String {
+    format!("{}, {}  {}", point.0, point.1, format.format(color))
+}
+
+/// An image position with its straight RGBA colour.
+type Pixel = ((i32, i32), [u8; 4]);
+
+/// Reads the image pixel below the widget coordinates `x`, `y`.
+fn pick_pixel(
+    image: &RefCell<Option<Document>>,
+    surface_cache: &RefCell<Option<(Texture, ImageSurface)>>,
+    scale: f64,
+    x: f64,
+    y: f64,
+) -> Result<Option<Pixel>> {
+    let image = image.borrow();
+    let document = image
+        .as_ref()
+        .ok_or_else(|| anyhow!("No screenshot available to pick a colour from"))?;
+    let mut surface_cache = surface_cache.borrow_mut();
+    if surface_cache.as_ref().map(|(texture, _)| texture) != Some(document.texture()) {
+        *surface_cache = Some((
+            document.texture().clone(),
+            surface_from_texture(document.texture())?,
+        ));
+    }
+    let Some((_, surface)) = surface_cache.as_mut() else {
+        return Ok(None);
+    };
+    let point = ((x / scale).floor() as i32, (y / scale).floor() as i32);
+    Ok(pixel_at(surface, point.0, point.1)?.map(|color| (point, color)))
+}
+
+pub(crate) fn connect_inspect(
+    layer: &DrawingArea,
+    image: &Rc<RefCell<Option<Document>>>,
+    tool_drop: &DropDown,
+    format_drop: &DropDown,
+    pixel_label: &Label,
+    scale: &Rc<Cell<f64>>,
+    clipboard: &Clipboard,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) {
+    let hovered: Rc<Cell<Option<(i32, i32)>>> = Rc::new(Cell::new(None));
+    let surface_cache: Rc<RefCell<Option<(Texture, ImageSurface)>>> = Rc::new(RefCell::new(None));
+
+    target_tools(layer, tool_drop, |tool| tool == Tool::Inspect);
+    tool_drop.connect_selected_notify(clone!(@strong hovered, @weak layer => move |_| {
+        hovered.set(None);
+        layer.queue_draw();
+    }));
+
+    let pick = Rc::new(
+        clone!(@strong image, @strong surface_cache, @strong scale => move |x: f64, y: f64| {
+            pick_pixel(&image, &surface_cache, scale.get(), x, y)
+        }),
+    );
+
+    layer.set_draw_func(clone!(@strong hovered, @strong scale => move |_, cr, _, _| {
+        if let Some((x, y)) = hovered.get() {
+            let scale = scale.get();
+            let size = scale.max(1.0);
+            cr.set_line_width(1.0);
+            cr.rectangle(x as f64 * scale - 1.5, y as f64 * scale - 1.5, size + 3.0, size + 3.0);
+            cr.set_source_rgb(0.0, 0.0, 0.0);
+            let _ = cr.stroke();
+            cr.rectangle(x as f64 * scale - 0.5, y as f64 * scale - 0.5, size + 1.0, size + 1.0);
+            cr.set_source_rgb(1.0, 1.0, 1.0);
+            let _ = cr.stroke();
+        }
+    }));
+
+    let motion = EventControllerMotion::new();
+    motion.connect_motion(clone!(
+        @strong pick,
+        @strong hovered,
+        @weak layer,
+        @weak format_drop,
+        @weak pixel_label
+            => move |_, x, y| {
+                let picked = pick(x, y).ok().flatten();
+                hovered.set(picked.map(|(point, _)| point));
+                if let Some((point, color)) = picked {
+                    pixel_label.set_text(&describe(point, color, ColorFormat::from_selected(&format_drop)));
+                }
+                layer.queue_draw();
+            }
+    ));
+    motion.connect_leave(clone!(@strong hovered, @weak layer => move |_| {
+        hovered.set(None);
+        layer.queue_draw();
+    }));
+    layer.add_controller(motion);
+
+    let click = GestureClick::new();
+    click.connect_released(clone!(
+        @strong pick,
+        @weak format_drop,
+        @weak pixel_label,
+        @strong clipboard,
+        @strong error_revealer,
+        @strong error_label
+            => move |_, _, x, y| {
+                match pick(x, y) {
+                    Ok(Some((point, color))) => {
+                        let format = ColorFormat::from_selected(&format_drop);
+                        clipboard.set_text(&format.format(color));
+                        pixel_label.set_text(&format!("{} (copied)", describe(point, color, format)));
+                    }
+                    Ok(None) => {}
+                    Err(e) => show_error(e, &error_revealer, &error_label),
+                }
+            }
+    ));
+    layer.add_controller(click);
+}
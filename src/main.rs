@@ -8,15 +8,23 @@ use std::{
 };
 
 use gtk4::{
-    gdk::{prelude::DisplayExt, Display, Key, ModifierType},
-    gio::Subprocess,
-    glib::{self, clone, Bytes, MainContext, Propagation},
-    prelude::{ApplicationExt, ApplicationExtManual},
+    gdk::{prelude::DisplayExt, Display, Key, ModifierType, RGBA},
+    gio::{File, Subprocess},
+    glib::{self, clone, MainContext, Propagation},
+    prelude::{ApplicationExt, ApplicationExtManual, Cast, TextViewExt},
     style_context_add_provider_for_display,
     traits::{BoxExt, ButtonExt, GtkWindowExt, WidgetExt},
-    Adjustment, AlternativeTrigger, Application, ApplicationWindow, Box, Button, CallbackAction,
-    CheckButton, CssProvider, KeyvalTrigger, Label, Picture, Revealer, ScrolledWindow, Shortcut,
-    ShortcutController, SpinButton, STYLE_PROVIDER_PRIORITY_APPLICATION,
+    Adjustment, Align, AlternativeTrigger, Application, ApplicationWindow, Box, Button,
+    CallbackAction, CheckButton, ColorDialog, ColorDialogButton, CssProvider, DrawingArea,
+    DropDown, Entry, KeyvalTrigger, Label, Overlay, Picture, Revealer, ScrolledWindow, Shortcut,
+    ShortcutController, SpinButton, TextView, STYLE_PROVIDER_PRIORITY_APPLICATION,
+};
+
+use crate::{
+    annotate::Tool,
+    editor::Document,
+    history::HistoryAction,
+    zoom::{Zoom, ZoomView},
 };
 
 struct KillSubprocessGuard {
@@ -39,15 +47,53 @@ impl KillSubprocessGuard {
 enum ShotType {
     Fullscreen,
     Selection,
+    PickColor,
+    Text,
+    Code,
+    Recording { selection: bool },
+    Animation,
+    Burst { selection: bool },
+    Scrolling,
 }
 
 mod activate;
+mod animation;
+mod annotate;
+mod auto_save;
+mod burst;
+mod command;
+mod config;
+mod copy_path;
+mod crop;
+mod doctor;
+mod drag_image;
+mod editor;
+mod freeze;
+mod gsettings;
+mod history;
+mod inspect;
+mod measure;
+mod ocr;
+mod pick_color;
+mod preferences;
+mod recording;
+mod redact;
+mod resize;
 mod save_to_file;
+mod scan_code;
+mod scroll_capture;
 mod set_clipboard;
 mod snap_full;
 mod snap_selection;
+mod tools;
+mod trim;
+mod zoom;
 
 fn main() -> anyhow::Result<()> {
+    if std::env::args().skip(1).any(|arg| arg == "--check") {
+        tools::set(config::load_tools()?);
+        exit(doctor::print_report());
+    }
     let listener = Cell::new(Some(activate::activate_or_open()?));
 
     let app = Application::builder().application_id("com.shots").build();
@@ -66,6 +112,15 @@ fn main() -> anyhow::Result<()> {
     exit(app.run().value())
 }
 
+fn crop_spin_button() -> SpinButton {
+    SpinButton::builder()
+        .numeric(true)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(0.0, 0.0, 16384.0, 1.0, 10.0, 0.0))
+        .build()
+}
+
 fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
     let listener = listener.take().unwrap();
     let window = ApplicationWindow::builder()
@@ -120,12 +175,290 @@ fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
     let cursor_label = Label::new(Some("Include Cursor"));
     let cursor_check = CheckButton::new();
 
+    let freeze_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let freeze_label = Label::new(Some("Freeze Screen while Selecting"));
+    let freeze_check = CheckButton::builder().active(true).build();
+
+    let copy_after_save_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let copy_after_save_label = Label::new(Some("Copy Path after Saving"));
+    let copy_after_save_check = CheckButton::new();
+
+    let path_format_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let path_format_label = Label::new(Some("Path Format"));
+    let path_format_drop = DropDown::from_strings(&["Plain Path", "File URI"]);
+
     let capture_box = Box::builder()
         .css_classes(["setting", "buttons"])
         .orientation(gtk4::Orientation::Horizontal)
         .build();
     let capture_full = Button::with_label("Full Screen");
     let capture_selection = Button::with_label("Selection");
+    let capture_color = Button::with_label("Pick Colour");
+    let capture_text = Button::with_label("Capture Text");
+    let capture_code = Button::with_label("Scan Selection");
+    let capture_scrolling = Button::with_label("Scrolling");
+
+    let record_box = Box::builder()
+        .css_classes(["setting", "buttons"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let record_format_drop = DropDown::from_strings(&recording::VideoFormat::NAMES);
+    let record_screen = Button::with_label("Record Screen");
+    let record_selection = Button::with_label("Record Selection");
+    let animation_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let animation_fps_label = Label::new(Some("Animate at"));
+    let animation_fps = SpinButton::builder()
+        .numeric(true)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(10.0, 1.0, 30.0, 1.0, 5.0, 0.0))
+        .build();
+    let animation_seconds_label = Label::new(Some("fps for"));
+    let animation_seconds = SpinButton::builder()
+        .numeric(true)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(5.0, 1.0, 30.0, 1.0, 5.0, 0.0))
+        .build();
+    let animation_format_drop = DropDown::from_strings(&animation::AnimationFormat::NAMES);
+    let animation_record = Button::with_label("Record Animation");
+    let animation_settings = animation::AnimationSettings {
+        fps: animation_fps.clone(),
+        seconds: animation_seconds.clone(),
+        format_drop: animation_format_drop.clone(),
+    };
+    let burst_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let burst_count_label = Label::new(Some("Take"));
+    let burst_count = SpinButton::builder()
+        .numeric(true)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(10.0, 2.0, 999.0, 1.0, 10.0, 0.0))
+        .build();
+    let burst_interval_label = Label::new(Some("captures every"));
+    let burst_interval = SpinButton::builder()
+        .numeric(true)
+        .digits(1)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(2.0, 0.5, 3600.0, 0.5, 10.0, 0.0))
+        .build();
+    let burst_seconds_label = Label::new(Some("s"));
+    let burst_screen = Button::with_label("Burst Screen");
+    let burst_selection = Button::with_label("Burst Selection");
+    let burst_settings = burst::BurstSettings {
+        count: burst_count.clone(),
+        interval: burst_interval.clone(),
+        running: Rc::new(RefCell::new(None)),
+    };
+    let recording_revealer = Revealer::new();
+    let recording_box = Box::builder()
+        .css_classes(["setting", "buttons"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let recording_save = Button::with_label("Save Recording");
+    let recording_copy = Button::with_label("Copy Recording");
+    let recording_controls = recording::RecordingControls {
+        recorder: Rc::new(RefCell::new(None)),
+        recorded: Rc::new(RefCell::new(None)),
+        format_drop: record_format_drop.clone(),
+        revealer: recording_revealer.clone(),
+    };
+
+    let tool_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let tool_label = Label::new(Some("Tool"));
+    let tool_drop = DropDown::from_strings(&Tool::names());
+
+    let color_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let color_label = Label::new(Some("Colour"));
+    let color_button = ColorDialogButton::builder()
+        .dialog(&ColorDialog::new())
+        .rgba(&RGBA::RED)
+        .build();
+
+    let stroke_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let stroke_label = Label::new(Some("Stroke Width"));
+    let stroke_button = SpinButton::builder()
+        .numeric(true)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(4.0, 1.0, 64.0, 1.0, 4.0, 0.0))
+        .build();
+
+    let color_format_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let color_format_label = Label::new(Some("Colour Format"));
+    let color_format_drop = DropDown::from_strings(&inspect::COLOR_FORMAT_NAMES);
+    let pixel_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let pixel_label = Label::new(Some("Pick a colour from the preview"));
+
+    let measure_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let capture_scale_label = Label::new(Some("Capture Scale"));
+    let capture_scale_button = SpinButton::builder()
+        .numeric(true)
+        .digits(2)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(1.0, 0.25, 8.0, 0.25, 1.0, 0.0))
+        .build();
+    let measure_label = Label::new(Some("Drag with the ruler to measure"));
+
+    let crop_revealer = Revealer::new();
+    let crop_box = Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .build();
+    let aspect_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let aspect_label = Label::new(Some("Aspect Ratio"));
+    let aspect_drop = DropDown::from_strings(&crop::ASPECT_NAMES);
+    let crop_position_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let crop_position_label = Label::new(Some("Position"));
+    let crop_x = crop_spin_button();
+    let crop_y = crop_spin_button();
+    let crop_size_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let crop_size_label = Label::new(Some("Size"));
+    let crop_width = crop_spin_button();
+    let crop_height = crop_spin_button();
+    let crop_apply = Button::with_label("Crop");
+
+    let trim_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let trim_label = Label::new(Some("Border Tolerance"));
+    let trim_tolerance = SpinButton::builder()
+        .numeric(true)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(8.0, 0.0, 255.0, 1.0, 8.0, 0.0))
+        .build();
+    let trim_apply = Button::with_label("Auto Crop");
+
+    let edit_box = Box::builder()
+        .css_classes(["setting", "buttons"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let edit_undo = Button::with_label("Undo");
+    let edit_redo = Button::with_label("Redo");
+    let edit_revert = Button::with_label("Revert");
+    let edit_resize = Button::with_label("Resize");
+
+    let magnification_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let magnification_label = Label::new(Some("Magnification"));
+    let magnification_button = SpinButton::builder()
+        .numeric(true)
+        .digits(1)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(3.0, 1.5, 16.0, 0.5, 1.0, 0.0))
+        .build();
+
+    let zoom_box = Box::builder()
+        .css_classes(["setting", "buttons"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let zoom_fit = Button::with_label("Fit");
+    let zoom_actual = Button::with_label("100%");
+    let zoom_out = Button::with_label("−");
+    let zoom_in = Button::with_label("+");
+    let zoom_label = Label::new(Some("100%"));
+
+    let ocr_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let ocr_language_label = Label::new(Some("Text Language"));
+    let ocr_language = Entry::builder().text("eng").width_chars(8).build();
+    let ocr_extract = Button::with_label("Extract Text");
+    let ocr_revealer = Revealer::new();
+    let ocr_panel_box = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Vertical)
+        .build();
+    let ocr_scroll = ScrolledWindow::builder()
+        .min_content_height(120)
+        .max_content_width(320)
+        .build();
+    let ocr_text = TextView::builder()
+        .wrap_mode(gtk4::WrapMode::WordChar)
+        .monospace(true)
+        .build();
+    let ocr_copy = Button::with_label("Copy Text");
+    let ocr_panel = ocr::OcrPanel {
+        revealer: ocr_revealer.clone(),
+        buffer: ocr_text.buffer(),
+        language: ocr_language.clone(),
+    };
+
+    let code_box = Box::builder()
+        .css_classes(["setting", "buttons"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let code_scan = Button::with_label("Scan Code");
+    let code_revealer = Revealer::new();
+    let code_list = Box::builder()
+        .orientation(gtk4::Orientation::Vertical)
+        .build();
+
+    let doctor_box = Box::builder()
+        .css_classes(["setting", "buttons"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let doctor_check = Button::with_label("Check Dependencies");
+    let preferences_open = Button::with_label("Preferences");
+    let preferences_window = preferences::build_window(&window);
+    let editor_defaults = preferences::EditorDefaults::new();
+    let backends = preferences::BackendEntries::new();
+    let save_directory = preferences::SaveDirectory::new();
+    let doctor_revealer = Revealer::new();
+    let doctor_label = Label::builder()
+        .css_classes(["setting"])
+        .selectable(true)
+        .xalign(0.0)
+        .build();
 
     let save_box = Box::builder()
         .css_classes(["setting", "buttons"])
@@ -133,6 +466,7 @@ fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
         .build();
     let save_file = Button::with_label("Save to File");
     let save_clip = Button::with_label("Copy to Clipboard");
+    let save_path = Button::with_label("Copy Path");
 
     let image_revealer = Revealer::builder()
         .css_classes(["image_revealer"])
@@ -142,34 +476,131 @@ fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
     let image_scroll = ScrolledWindow::builder()
         .css_classes(["image_scroll"])
         .build();
-    let image_view = Picture::builder()
-        .can_shrink(false)
-        .css_classes(["image"])
+    let image_overlay = Overlay::builder()
+        .halign(Align::Center)
+        .valign(Align::Center)
         .build();
+    let image_frame = DrawingArea::new();
+    let image_view = Picture::builder().css_classes(["image"]).build();
+    let image_pixels = DrawingArea::new();
+    let image_canvas = DrawingArea::builder().css_classes(["canvas"]).build();
+    let image_crop_layer = DrawingArea::new();
+    let image_inspect_layer = DrawingArea::new();
+    let image_measure_layer = DrawingArea::new();
 
-    let image: Rc<RefCell<Option<Bytes>>> = Rc::new(RefCell::new(None));
+    let image: Rc<RefCell<Option<Document>>> = Rc::new(RefCell::new(None));
+    let saved_file: Rc<RefCell<Option<File>>> = Rc::new(RefCell::new(None));
     let last_shot: Rc<Cell<ShotType>> = Rc::new(Cell::new(ShotType::Selection));
+    let scale: Rc<Cell<f64>> = Rc::new(Cell::new(1.0));
+    let scrolling: Rc<Cell<bool>> = Rc::new(Cell::new(false));
 
     let main_context = MainContext::default();
 
     let display = Display::default().expect("could not connect to display");
     let clipboard = display.clipboard();
+    let code_panel = scan_code::CodePanel {
+        revealer: code_revealer.clone(),
+        list: code_list.clone(),
+        clipboard: clipboard.clone(),
+        window: window.clone(),
+    };
 
     let shortcuts = ShortcutController::new();
 
     main_context.spawn_local(activate::wait_for_activation(
+        main_context.clone(),
         last_shot.clone(),
         window.clone(),
         listener,
         image.clone(),
         image_view.clone(),
         image_revealer.clone(),
+        saved_file.clone(),
         delay_button.clone(),
         cursor_check.clone(),
+        freeze_check.clone(),
+        color_format_drop.clone(),
+        pixel_label.clone(),
+        clipboard.clone(),
+        ocr_panel.clone(),
+        code_panel.clone(),
+        recording_controls.clone(),
+        animation_settings.clone(),
+        burst_settings.clone(),
+        scrolling.clone(),
         error_revealer.clone(),
         error_label.clone(),
     ));
 
+    editor_defaults.connect_editor(&color_button, &stroke_button, &magnification_button);
+    config::connect_config(
+        &config::ConfigWidgets {
+            window: window.clone(),
+            last_shot: last_shot.clone(),
+            delay_button: delay_button.clone(),
+            cursor_check: cursor_check.clone(),
+            freeze_check: freeze_check.clone(),
+            copy_after_save_check: copy_after_save_check.clone(),
+            save_directory: save_directory.entry.clone(),
+            path_format_drop: path_format_drop.clone(),
+            color_format_drop: color_format_drop.clone(),
+            text_language: ocr_language.clone(),
+            record_format_drop: record_format_drop.clone(),
+            animation: animation_settings.clone(),
+            burst: burst_settings.clone(),
+            editor: editor_defaults.clone(),
+            backends: backends.clone(),
+            desktop: gsettings::settings().is_some(),
+        },
+        &error_revealer,
+        &error_label,
+    );
+    gsettings::bind_settings(&gsettings::SettingsWidgets {
+        delay_button: delay_button.clone(),
+        cursor_check: cursor_check.clone(),
+        freeze_check: freeze_check.clone(),
+        copy_after_save_check: copy_after_save_check.clone(),
+        path_format_drop: path_format_drop.clone(),
+        save_directory: save_directory.entry.clone(),
+    });
+    doctor::connect_doctor(
+        &main_context,
+        &doctor::DoctorPanel {
+            revealer: doctor_revealer.clone(),
+            label: doctor_label.clone(),
+            buttons: Rc::new(vec![
+                (doctor::Mode::Fullscreen, capture_full.clone()),
+                (doctor::Mode::Selection, capture_selection.clone()),
+                (doctor::Mode::Selection, capture_color.clone()),
+                (doctor::Mode::Selection, capture_scrolling.clone()),
+                (doctor::Mode::Fullscreen, burst_screen.clone()),
+                (doctor::Mode::Selection, burst_selection.clone()),
+                (doctor::Mode::Selection, animation_record.clone()),
+                (doctor::Mode::CaptureText, capture_text.clone()),
+                (doctor::Mode::ExtractText, ocr_extract.clone()),
+                (doctor::Mode::CaptureCode, capture_code.clone()),
+                (doctor::Mode::ScanImage, code_scan.clone()),
+                (doctor::Mode::RecordScreen, record_screen.clone()),
+                (doctor::Mode::RecordSelection, record_selection.clone()),
+            ]),
+            freeze_check: freeze_check.clone(),
+        },
+        &doctor_check,
+    );
+    drag_image::connect_drag(&image, &image_view, &error_revealer, &error_label);
+    annotate::connect_canvas(
+        &image_canvas,
+        &image,
+        &image_view,
+        &tool_drop,
+        &color_button,
+        &stroke_button,
+        &magnification_button,
+        &scale,
+        &error_revealer,
+        &error_label,
+    );
+
     error_close.connect_clicked(clone!(@weak error_revealer => move|_|{
         error_revealer.set_reveal_child(false);
     }));
@@ -193,14 +624,256 @@ fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
         &image_revealer,
         &delay_button,
         &cursor_check,
+        &freeze_check,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    capture_color.connect_clicked(pick_color::get_handler(
+        &last_shot,
+        &main_context,
+        &delay_button,
+        &freeze_check,
+        &color_format_drop,
+        &pixel_label,
+        &clipboard,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    capture_text.connect_clicked(ocr::get_capture_handler(
+        &last_shot,
+        &main_context,
+        &ocr_panel,
+        &delay_button,
+        &freeze_check,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    ocr_extract.connect_clicked(ocr::get_handler(
+        &main_context,
+        &image,
+        &ocr_panel,
+        &error_revealer,
+        &error_label,
+    ));
+    ocr_copy.connect_clicked(ocr::get_copy_handler(&ocr_panel, &clipboard));
+    capture_code.connect_clicked(scan_code::get_capture_handler(
+        &last_shot,
+        &main_context,
+        &code_panel,
+        &delay_button,
+        &freeze_check,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    code_scan.connect_clicked(scan_code::get_handler(
+        &main_context,
+        &image,
+        &code_panel,
+        &error_revealer,
+        &error_label,
+    ));
+    record_screen.connect_clicked(recording::get_handler(
+        false,
+        &last_shot,
+        &main_context,
+        &recording_controls,
+        &image,
+        &image_view,
+        &image_revealer,
+        &delay_button,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    record_selection.connect_clicked(recording::get_handler(
+        true,
+        &last_shot,
+        &main_context,
+        &recording_controls,
+        &image,
+        &image_view,
+        &image_revealer,
+        &delay_button,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    animation_record.connect_clicked(animation::get_handler(
+        &last_shot,
+        &main_context,
+        &animation_settings,
+        &recording_controls,
+        &image,
+        &image_view,
+        &image_revealer,
+        &delay_button,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    burst_screen.connect_clicked(burst::get_handler(
+        false,
+        &last_shot,
+        &main_context,
+        &burst_settings,
+        &image,
+        &image_view,
+        &image_revealer,
+        &saved_file,
+        &delay_button,
+        &cursor_check,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    burst_selection.connect_clicked(burst::get_handler(
+        true,
+        &last_shot,
+        &main_context,
+        &burst_settings,
+        &image,
+        &image_view,
+        &image_revealer,
+        &saved_file,
+        &delay_button,
+        &cursor_check,
+        &error_revealer,
+        &error_label,
+        &window,
+    ));
+    capture_scrolling.connect_clicked(scroll_capture::get_handler(
+        &last_shot,
+        &main_context,
+        &scrolling,
+        &image,
+        &image_view,
+        &image_revealer,
+        &delay_button,
         &error_revealer,
         &error_label,
         &window,
     ));
+    recording_save.connect_clicked(recording::get_save_handler(
+        &main_context,
+        &window,
+        &recording_controls,
+        &saved_file,
+        &error_revealer,
+        &error_label,
+    ));
+    recording_copy.connect_clicked(recording::get_copy_handler(
+        &recording_controls,
+        &clipboard,
+        &error_revealer,
+        &error_label,
+    ));
+    crop::connect_crop(
+        &image_crop_layer,
+        &image,
+        &image_view,
+        &tool_drop,
+        &crop_revealer,
+        &aspect_drop,
+        &crop_x,
+        &crop_y,
+        &crop_width,
+        &crop_height,
+        &crop_apply,
+        &scale,
+        &error_revealer,
+        &error_label,
+    );
+
+    inspect::connect_inspect(
+        &image_inspect_layer,
+        &image,
+        &tool_drop,
+        &color_format_drop,
+        &pixel_label,
+        &scale,
+        &clipboard,
+        &error_revealer,
+        &error_label,
+    );
+
+    capture_scale_button.set_value(measure::display_scale(&display));
+    measure::connect_measure(
+        &image_measure_layer,
+        &image,
+        &tool_drop,
+        &capture_scale_button,
+        &measure_label,
+        &scale,
+    );
+
+    let zoom_view = ZoomView::new(
+        &image_scroll,
+        &image_view,
+        &image_frame,
+        &image_pixels,
+        &[
+            &image_canvas,
+            &image_crop_layer,
+            &image_inspect_layer,
+            &image_measure_layer,
+        ],
+        &zoom_label,
+        &scale,
+    );
+    zoom::connect_zoom(&zoom_view, &image, &tool_drop);
+    zoom_fit.connect_clicked(clone!(@strong zoom_view => move |_| zoom_view.set(Zoom::Fit)));
+    zoom_actual
+        .connect_clicked(clone!(@strong zoom_view => move |_| zoom_view.set(Zoom::Scale(1.0))));
+    zoom_out.connect_clicked(clone!(@strong zoom_view => move |_| zoom_view.zoom_out()));
+    zoom_in.connect_clicked(clone!(@strong zoom_view => move |_| zoom_view.zoom_in()));
+
+    edit_undo.connect_clicked(history::get_handler(
+        HistoryAction::Undo,
+        &image,
+        &image_view,
+        &error_revealer,
+        &error_label,
+    ));
+    edit_redo.connect_clicked(history::get_handler(
+        HistoryAction::Redo,
+        &image,
+        &image_view,
+        &error_revealer,
+        &error_label,
+    ));
+    edit_revert.connect_clicked(history::get_handler(
+        HistoryAction::Revert,
+        &image,
+        &image_view,
+        &error_revealer,
+        &error_label,
+    ));
+    edit_resize.connect_clicked(resize::get_handler(
+        &window,
+        &image,
+        &image_view,
+        &error_revealer,
+        &error_label,
+    ));
+    trim_apply.connect_clicked(trim::get_handler(
+        &image,
+        &image_view,
+        &trim_tolerance,
+        &error_revealer,
+        &error_label,
+    ));
     save_file.connect_clicked(save_to_file::get_handler(
         &main_context,
         &window,
         &image,
+        &saved_file,
+        &copy_after_save_check,
+        &path_format_drop,
+        &clipboard,
         &error_revealer,
         &error_label,
     ));
@@ -210,6 +883,13 @@ fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
         &error_revealer,
         &error_label,
     ));
+    save_path.connect_clicked(copy_path::get_handler(
+        &saved_file,
+        &path_format_drop,
+        &clipboard,
+        &error_revealer,
+        &error_label,
+    ));
 
     shortcuts.add_shortcut(
         Shortcut::builder()
@@ -226,12 +906,120 @@ fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
             .build(),
     );
 
+    shortcuts.add_shortcut(
+        Shortcut::builder()
+            .trigger(&KeyvalTrigger::new(Key::comma, ModifierType::CONTROL_MASK))
+            .action(&CallbackAction::new(
+                clone!(@weak preferences_window => @default-return false, move |_,_|{
+                    preferences_window.present();
+                    true
+                }),
+            ))
+            .build(),
+    );
+    preferences_open.connect_clicked(clone!(@weak preferences_window => move |_| {
+        preferences_window.present();
+    }));
+
+    shortcuts.add_shortcut(
+        Shortcut::builder()
+            .trigger(&KeyvalTrigger::new(Key::z, ModifierType::CONTROL_MASK))
+            .action(&CallbackAction::new(clone!(
+                @strong image,
+                @weak image_view,
+                @strong error_revealer,
+                @strong error_label
+                    => @default-return false, move |_,_|{
+                history::handler(HistoryAction::Undo, &image, &image_view, &error_revealer, &error_label);
+                true
+            })))
+            .build(),
+    );
+    shortcuts.add_shortcut(
+        Shortcut::builder()
+            .trigger(&KeyvalTrigger::new(
+                Key::z,
+                ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK,
+            ))
+            .action(&CallbackAction::new(clone!(
+                @strong image,
+                @weak image_view,
+                @strong error_revealer,
+                @strong error_label
+                    => @default-return false, move |_,_|{
+                history::handler(HistoryAction::Redo, &image, &image_view, &error_revealer, &error_label);
+                true
+            })))
+            .build(),
+    );
+
+    shortcuts.add_shortcut(
+        Shortcut::builder()
+            .trigger(&AlternativeTrigger::new(
+                AlternativeTrigger::new(
+                    KeyvalTrigger::new(Key::plus, ModifierType::empty()),
+                    KeyvalTrigger::new(Key::equal, ModifierType::empty()),
+                ),
+                KeyvalTrigger::new(Key::KP_Add, ModifierType::empty()),
+            ))
+            .action(&CallbackAction::new(
+                clone!(@strong zoom_view => @default-return false, move |_,_|{
+                    zoom_view.zoom_in();
+                    true
+                }),
+            ))
+            .build(),
+    );
+    shortcuts.add_shortcut(
+        Shortcut::builder()
+            .trigger(&AlternativeTrigger::new(
+                KeyvalTrigger::new(Key::minus, ModifierType::empty()),
+                KeyvalTrigger::new(Key::KP_Subtract, ModifierType::empty()),
+            ))
+            .action(&CallbackAction::new(
+                clone!(@strong zoom_view => @default-return false, move |_,_|{
+                    zoom_view.zoom_out();
+                    true
+                }),
+            ))
+            .build(),
+    );
+    shortcuts.add_shortcut(
+        Shortcut::builder()
+            .trigger(&KeyvalTrigger::new(Key::_1, ModifierType::empty()))
+            .action(&CallbackAction::new(
+                clone!(@strong zoom_view => @default-return false, move |_,_|{
+                    zoom_view.set(Zoom::Scale(1.0));
+                    true
+                }),
+            ))
+            .build(),
+    );
+    shortcuts.add_shortcut(
+        Shortcut::builder()
+            .trigger(&KeyvalTrigger::new(Key::_0, ModifierType::empty()))
+            .action(&CallbackAction::new(
+                clone!(@strong zoom_view => @default-return false, move |_,_|{
+                    zoom_view.set(Zoom::Fit);
+                    true
+                }),
+            ))
+            .build(),
+    );
+
     window.connect_close_request(
         clone!(@weak window => @default-return Propagation::Proceed, move |_| {
             window.set_visible(false);
             Propagation::Stop
         }),
     );
+    // `exit` skips destructors, so the temporary files are removed while shutting down
+    app.connect_shutdown(
+        clone!(@strong image, @strong recording_controls => move |_| {
+            image.replace(None);
+            recording_controls.recorded.replace(None);
+        }),
+    );
 
     window.add_controller(shortcuts);
 
@@ -243,23 +1031,181 @@ fn build_ui(app: &Application, listener: &Cell<Option<UnixListener>>) {
     delay_box.append(&delay_label1);
     delay_box.append(&delay_button);
     delay_box.append(&delay_label2);
-    settings.append(&delay_box);
 
     cursor_box.append(&cursor_label);
     cursor_box.append(&cursor_check);
-    settings.append(&cursor_box);
+
+    freeze_box.append(&freeze_label);
+    freeze_box.append(&freeze_check);
+
+    copy_after_save_box.append(&copy_after_save_label);
+    copy_after_save_box.append(&copy_after_save_check);
+
+    path_format_box.append(&path_format_label);
+    path_format_box.append(&path_format_drop);
+
+    color_format_box.append(&color_format_label);
+    color_format_box.append(&color_format_drop);
+
+    let backend_note = Label::builder()
+        .label("Empty paths run the tools from PATH")
+        .css_classes(["setting"])
+        .xalign(0.0)
+        .build();
+    preferences::fill_window(
+        &preferences_window,
+        vec![
+            (
+                "Capture",
+                vec![delay_box.upcast(), cursor_box.upcast(), freeze_box.upcast()],
+            ),
+            (
+                "Saving",
+                vec![save_directory.row(&main_context, &preferences_window)],
+            ),
+            (
+                "Clipboard",
+                vec![
+                    copy_after_save_box.upcast(),
+                    path_format_box.upcast(),
+                    color_format_box.upcast(),
+                ],
+            ),
+            ("Editor Defaults", editor_defaults.rows()),
+            ("Shortcuts", preferences::shortcut_rows()),
+            (
+                "Backends",
+                std::iter::once(backend_note.upcast())
+                    .chain(backends.rows())
+                    .collect(),
+            ),
+        ],
+    );
 
     capture_box.append(&capture_full);
     capture_box.append(&capture_selection);
+    capture_box.append(&capture_color);
+    capture_box.append(&capture_text);
+    capture_box.append(&capture_code);
+    capture_box.append(&capture_scrolling);
     settings.append(&capture_box);
+    doctor_box.append(&doctor_check);
+    doctor_box.append(&preferences_open);
+    settings.append(&doctor_box);
+    doctor_revealer.set_child(Some(&doctor_label));
+    settings.append(&doctor_revealer);
+
+    record_box.append(&record_format_drop);
+    record_box.append(&record_screen);
+    record_box.append(&record_selection);
+    settings.append(&record_box);
+    animation_box.append(&animation_fps_label);
+    animation_box.append(&animation_fps);
+    animation_box.append(&animation_seconds_label);
+    animation_box.append(&animation_seconds);
+    animation_box.append(&animation_format_drop);
+    animation_box.append(&animation_record);
+    settings.append(&animation_box);
+    burst_box.append(&burst_count_label);
+    burst_box.append(&burst_count);
+    burst_box.append(&burst_interval_label);
+    burst_box.append(&burst_interval);
+    burst_box.append(&burst_seconds_label);
+    burst_box.append(&burst_screen);
+    burst_box.append(&burst_selection);
+    settings.append(&burst_box);
+    recording_box.append(&recording_save);
+    recording_box.append(&recording_copy);
+    recording_revealer.set_child(Some(&recording_box));
+    settings.append(&recording_revealer);
+
+    tool_box.append(&tool_label);
+    tool_box.append(&tool_drop);
+    settings.append(&tool_box);
+
+    color_box.append(&color_label);
+    color_box.append(&color_button);
+    settings.append(&color_box);
+
+    stroke_box.append(&stroke_label);
+    stroke_box.append(&stroke_button);
+    settings.append(&stroke_box);
+
+    magnification_box.append(&magnification_label);
+    magnification_box.append(&magnification_button);
+    settings.append(&magnification_box);
+
+    pixel_box.append(&pixel_label);
+    settings.append(&pixel_box);
+
+    measure_box.append(&capture_scale_label);
+    measure_box.append(&capture_scale_button);
+    measure_box.append(&measure_label);
+    settings.append(&measure_box);
+
+    aspect_box.append(&aspect_label);
+    aspect_box.append(&aspect_drop);
+    crop_box.append(&aspect_box);
+    crop_position_box.append(&crop_position_label);
+    crop_position_box.append(&crop_x);
+    crop_position_box.append(&crop_y);
+    crop_box.append(&crop_position_box);
+    crop_size_box.append(&crop_size_label);
+    crop_size_box.append(&crop_width);
+    crop_size_box.append(&crop_height);
+    crop_box.append(&crop_size_box);
+    crop_box.append(&crop_apply);
+    crop_revealer.set_child(Some(&crop_box));
+    settings.append(&crop_revealer);
+
+    trim_box.append(&trim_label);
+    trim_box.append(&trim_tolerance);
+    trim_box.append(&trim_apply);
+    settings.append(&trim_box);
+
+    edit_box.append(&edit_undo);
+    edit_box.append(&edit_redo);
+    edit_box.append(&edit_revert);
+    edit_box.append(&edit_resize);
+    settings.append(&edit_box);
+
+    zoom_box.append(&zoom_fit);
+    zoom_box.append(&zoom_actual);
+    zoom_box.append(&zoom_out);
+    zoom_box.append(&zoom_in);
+    zoom_box.append(&zoom_label);
+    settings.append(&zoom_box);
+
+    ocr_box.append(&ocr_language_label);
+    ocr_box.append(&ocr_language);
+    ocr_box.append(&ocr_extract);
+    settings.append(&ocr_box);
+    ocr_scroll.set_child(Some(&ocr_text));
+    ocr_panel_box.append(&ocr_scroll);
+    ocr_panel_box.append(&ocr_copy);
+    ocr_revealer.set_child(Some(&ocr_panel_box));
+    settings.append(&ocr_revealer);
+
+    code_box.append(&code_scan);
+    settings.append(&code_box);
+    code_revealer.set_child(Some(&code_list));
+    settings.append(&code_revealer);
 
     save_box.append(&save_file);
     save_box.append(&save_clip);
+    save_box.append(&save_path);
     settings.append(&save_box);
 
     horizontal.append(&settings);
 
-    image_scroll.set_child(Some(&image_view));
+    image_overlay.set_child(Some(&image_frame));
+    image_overlay.add_overlay(&image_view);
+    image_overlay.add_overlay(&image_pixels);
+    image_overlay.add_overlay(&image_canvas);
+    image_overlay.add_overlay(&image_crop_layer);
+    image_overlay.add_overlay(&image_inspect_layer);
+    image_overlay.add_overlay(&image_measure_layer);
+    image_scroll.set_child(Some(&image_overlay));
     image_revealer.set_child(Some(&image_scroll));
     horizontal.append(&image_revealer);
 
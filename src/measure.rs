@@ -0,0 +1,185 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk4::{
+    gdk::{Display, Monitor},
+    glib::{self, clone, Cast},
+    prelude::{
+        DisplayExt, DrawingAreaExtManual, GestureDragExt, ListModelExt, MonitorExt, WidgetExt,
+    },
+    DrawingArea, DropDown, GestureDrag, Label, SpinButton,
+};
+
+use crate::{
+    annotate::{target_tools, Tool},
+    editor::{Document, Point},
+};
+
+/// The scale grim captures at, which is the highest scale of all outputs.
+pub(crate) fn display_scale(display: &Display) -> f64 {
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|index| monitors.item(index)?.downcast::<Monitor>().ok())
+        .map(|monitor| monitor.scale_factor())
+        .max()
+        .unwrap_or(1) as f64
+}
+
+const LABEL_SIZE: f64 = 13.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Measurement {
+    start: Point,
+    end: Point,
+}
+
+impl Measurement {
+    /// Snaps both ends to pixel corners, so distances are whole image pixels.
+    fn snapped(start: Point, end: Point) -> Self {
+        Measurement {
+            start: (start.0.round(), start.1.round()),
+            end: (end.0.round(), end.1.round()),
+        }
+    }
+
+    fn dx(&self) -> f64 {
+        (self.end.0 - self.start.0).abs()
+    }
+
+    fn dy(&self) -> f64 {
+        (self.end.1 - self.start.1).abs()
+    }
+
+    fn distance(&self) -> f64 {
+        self.dx().hypot(self.dy())
+    }
+
+    fn describe(&self, capture_scale: f64) -> String {
+        format!(
+            "{:.1} px ({:.1} logical)\ndx {} px, dy {} px ({} × {} logical)",
+            self.distance(),
+            self.distance() / capture_scale,
+            self.dx(),
+            self.dy(),
+            logical(self.dx(), capture_scale),
+            logical(self.dy(), capture_scale),
+        )
+    }
+}
+
+fn logical(pixels: f64, capture_scale: f64) -> String {
+    let logical = pixels / capture_scale;
+    if logical.fract() == 0.0 {
+        format!("{logical}")
+    } else {
+        format!("{logical:.1}")
+    }
+}
+
+fn draw_measurement(cr: &gtk4::cairo::Context, measurement: Measurement, scale: f64) {
+    let start = (measurement.start.0 * scale, measurement.start.1 * scale);
+    let end = (measurement.end.0 * scale, measurement.end.1 * scale);
+
+    // bounding box
+    cr.set_line_width(1.0);
+    cr.set_dash(&[4.0, 4.0], 0.0);
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.8);
+    cr.rectangle(
+        start.0.min(end.0) + 0.5,
+        start.1.min(end.1) + 0.5,
+        (end.0 - start.0).abs(),
+        (end.1 - start.1).abs(),
+    );
+    let _ = cr.stroke();
+    cr.set_dash(&[], 0.0);
+
+    // ruler with an outline so it stays visible on any background
+    for (width, (red, green, blue)) in [(3.0, (0.0, 0.0, 0.0)), (1.0, (1.0, 0.9, 0.0))] {
+        cr.set_line_width(width);
+        cr.set_source_rgb(red, green, blue);
+        cr.move_to(start.0, start.1);
+        cr.line_to(end.0, end.1);
+        let _ = cr.stroke();
+    }
+
+    let text = format!("{:.0} px", measurement.distance());
+    cr.set_font_size(LABEL_SIZE);
+    let Ok(extents) = cr.text_extents(&text) else {
+        return;
+    };
+    let x = (start.0 + end.0) / 2.0 + 8.0;
+    let y = (start.1 + end.1) / 2.0 + 8.0;
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.7);
+    cr.rectangle(x, y, extents.width() + 8.0, extents.height() + 8.0);
+    let _ = cr.fill();
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.move_to(x + 4.0 - extents.x_bearing(), y + 4.0 - extents.y_bearing());
+    let _ = cr.show_text(&text);
+}
+
+pub(crate) fn connect_measure(
+    layer: &DrawingArea,
+    image: &Rc<RefCell<Option<Document>>>,
+    tool_drop: &DropDown,
+    capture_scale_button: &SpinButton,
+    measure_label: &Label,
+    scale: &Rc<Cell<f64>>,
+) {
+    let measurement: Rc<Cell<Option<Measurement>>> = Rc::new(Cell::new(None));
+
+    target_tools(layer, tool_drop, |tool| tool == Tool::Measure);
+    tool_drop.connect_selected_notify(clone!(@strong measurement, @weak layer => move |_| {
+        measurement.set(None);
+        layer.queue_draw();
+    }));
+
+    layer.set_draw_func(
+        clone!(@strong measurement, @strong scale => move |_, cr, _, _| {
+            if let Some(measurement) = measurement.get() {
+                draw_measurement(cr, measurement, scale.get());
+            }
+        }),
+    );
+
+    let show_measurement = Rc::new(clone!(
+        @weak layer,
+        @strong measurement,
+        @weak capture_scale_button,
+        @weak measure_label
+            => move |new_measurement: Option<Measurement>| {
+                measurement.set(new_measurement);
+                if let Some(new_measurement) = new_measurement {
+                    measure_label.set_text(&new_measurement.describe(capture_scale_button.value()));
+                }
+                layer.queue_draw();
+            }
+    ));
+    capture_scale_button.connect_value_changed(clone!(
+        @strong measurement,
+        @strong show_measurement
+            => move |_| show_measurement(measurement.get())
+    ));
+
+    let drag = GestureDrag::new();
+    drag.connect_drag_update(clone!(
+        @strong image,
+        @strong scale,
+        @strong show_measurement
+            => move |drag, x, y| {
+                let Some((start_x, start_y)) = drag.start_point() else {
+                    return;
+                };
+                if image.borrow().is_none() {
+                    return;
+                }
+                let scale = scale.get();
+                show_measurement(Some(Measurement::snapped(
+                    (start_x / scale, start_y / scale),
+                    ((start_x + x) / scale, (start_y + y) / scale),
+                )));
+            }
+    ));
+    layer.add_controller(drag);
+}
@@ -0,0 +1,187 @@
+use std::{
+    cell::{Cell, RefCell},
+    ffi::OsStr,
+    rc::Rc,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gdk::Clipboard,
+    glib::{self, clone, Bytes, MainContext},
+    prelude::{CheckButtonExt, EditableExt, TextBufferExt, WidgetExt},
+    ApplicationWindow, Button, CheckButton, Entry, Label, Revealer, SpinButton, TextBuffer,
+};
+
+use crate::{command::run, editor::Document, snap_selection::snap_selection, ShotType};
+
+const DEFAULT_LANGUAGE: &str = "eng";
+
+/// The editable panel showing recognized text before it is copied.
+#[derive(Clone)]
+pub(crate) struct OcrPanel {
+    pub(crate) revealer: Revealer,
+    pub(crate) buffer: TextBuffer,
+    pub(crate) language: Entry,
+}
+
+impl OcrPanel {
+    fn language(&self) -> String {
+        let language = self.language.text();
+        let language = language.trim();
+        if language.is_empty() {
+            DEFAULT_LANGUAGE.to_string()
+        } else {
+            language.to_string()
+        }
+    }
+
+    fn show(&self, text: &str) {
+        self.buffer.set_text(text.trim_end());
+        self.revealer.set_reveal_child(true);
+    }
+
+    fn text(&self) -> String {
+        self.buffer
+            .text(&self.buffer.start_iter(), &self.buffer.end_iter(), false)
+            .to_string()
+    }
+}
+
+/// Runs tesseract on a PNG image, `language` takes tesseract's syntax like `eng+deu`.
+async fn recognize(png: &Bytes, language: &str) -> Result<String> {
+    let out = run(
+        &[
+            OsStr::new("tesseract"),
+            OsStr::new("stdin"),
+            OsStr::new("stdout"),
+            OsStr::new("-l"),
+            OsStr::new(language),
+        ],
+        Some(png),
+    )
+    .await?;
+    String::from_utf8(out.to_vec()).context("decoding tesseract output")
+}
+
+async fn show_recognized(png: &Bytes, panel: &OcrPanel) -> Result<()> {
+    let text = recognize(png, &panel.language()).await?;
+    if text.trim().is_empty() {
+        return Err(anyhow!("No text was recognized in the screenshot"));
+    }
+    panel.show(&text);
+    Ok(())
+}
+
+/// Extracts the text of the current screenshot.
+pub(crate) fn get_handler(
+    main_context: &MainContext,
+    image: &Rc<RefCell<Option<Document>>>,
+    panel: &OcrPanel,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong main_context,
+        @strong image,
+        @strong panel,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                let png = image
+                    .borrow()
+                    .as_ref()
+                    .map(Document::bytes)
+                    .ok_or_else(|| anyhow!("No screenshot available to extract text from"));
+                main_context.spawn_local(clone!(
+                    @strong panel,
+                    @strong error_revealer,
+                    @strong error_label
+                        => async move {
+                            let result = match png {
+                                Ok(png) => show_recognized(&png, &panel).await,
+                                Err(e) => Err(e),
+                            };
+                            if let Err(e) = result {
+                                error_label.set_text(&format!("{e:?}"));
+                                error_revealer.set_reveal_child(true);
+                            }
+                        }
+                ));
+            }
+    )
+}
+
+/// Captures a selection and only keeps the text recognized in it.
+pub(crate) async fn handler_inner(
+    panel: &OcrPanel,
+    delay_button: &SpinButton,
+    freeze_check: &CheckButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) {
+    let result = async {
+        // the cursor would only get in the way of recognizing text
+        let png =
+            snap_selection(false, freeze_check.is_active(), delay_button.value() as u32).await?;
+        show_recognized(&png, panel).await
+    }
+    .await;
+    match result {
+        Ok(()) => error_revealer.set_reveal_child(false),
+        Err(e) => {
+            error_label.set_text(&format!("{:?}", e));
+            error_revealer.set_reveal_child(true);
+        }
+    }
+    window.set_visible(true);
+}
+
+pub(crate) fn get_capture_handler(
+    last_shot: &Rc<Cell<ShotType>>,
+    main_context: &MainContext,
+    panel: &OcrPanel,
+    delay_button: &SpinButton,
+    freeze_check: &CheckButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong last_shot,
+        @strong main_context,
+        @strong panel,
+        @strong delay_button,
+        @strong freeze_check,
+        @strong error_revealer,
+        @strong error_label,
+        @weak window
+            => move |_| {
+                window.set_visible(false);
+                last_shot.set(ShotType::Text);
+                main_context.spawn_local(clone!(
+                    @strong panel,
+                    @strong delay_button,
+                    @strong freeze_check,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @weak window
+                        => async move {
+                            handler_inner(
+                                &panel,
+                                &delay_button,
+                                &freeze_check,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+    )
+}
+
+pub(crate) fn get_copy_handler(panel: &OcrPanel, clipboard: &Clipboard) -> impl Fn(&Button) {
+    clone!(@strong panel, @strong clipboard => move |_| clipboard.set_text(&panel.text()))
+}
@@ -0,0 +1,127 @@
+use std::{cell::Cell, ffi::OsStr, rc::Rc, str::from_utf8};
+
+use gtk4::{
+    gdk::{Clipboard, Texture},
+    glib::{self, clone, timeout_future_seconds, MainContext},
+    traits::{CheckButtonExt, WidgetExt},
+    ApplicationWindow, Button, CheckButton, DropDown, Label, Revealer, SpinButton,
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{
+    command::run,
+    editor::surface_from_texture,
+    inspect::{pixel_at, ColorFormat},
+    ShotType,
+};
+
+/// Lets the user click a point on the screen and returns the colour of that pixel.
+async fn pick_color(freeze: bool, wait_seconds: u32) -> Result<[u8; 4]> {
+    timeout_future_seconds(wait_seconds).await;
+
+    // the cursor would cover the picked pixel
+    let _frozen = if freeze {
+        Some(crate::freeze::freeze(false).await?)
+    } else {
+        None
+    };
+
+    let point = run(&[OsStr::new("slurp"), OsStr::new("-p")], None).await?;
+    let point = from_utf8(&point)
+        .context("decoding slurp output")?
+        .trim_end();
+    let png = run(
+        &[
+            OsStr::new("grim"),
+            OsStr::new("-g"),
+            OsStr::new(point),
+            OsStr::new("-"),
+        ],
+        None,
+    )
+    .await?;
+
+    let texture = Texture::from_bytes(&png).context("decoding picked pixel")?;
+    let mut surface = surface_from_texture(&texture)?;
+    pixel_at(&mut surface, 0, 0)?.ok_or_else(|| anyhow!("grim returned an empty image"))
+}
+
+pub(crate) async fn handler_inner(
+    delay_button: &SpinButton,
+    freeze_check: &CheckButton,
+    format_drop: &DropDown,
+    pixel_label: &Label,
+    clipboard: &Clipboard,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) {
+    match pick_color(freeze_check.is_active(), delay_button.value() as u32).await {
+        Ok(color) => {
+            let color = ColorFormat::from_selected(format_drop).format(color);
+            clipboard.set_text(&color);
+            pixel_label.set_text(&format!("{color} (copied)"));
+            error_revealer.set_reveal_child(false);
+            window.set_visible(true);
+        }
+        Err(e) => {
+            error_label.set_text(&format!("{:?}", e));
+            error_revealer.set_reveal_child(true);
+            window.set_visible(true);
+        }
+    }
+}
+
+pub(crate) fn get_handler(
+    last_shot: &Rc<Cell<ShotType>>,
+    main_context: &MainContext,
+    delay_button: &SpinButton,
+    freeze_check: &CheckButton,
+    format_drop: &DropDown,
+    pixel_label: &Label,
+    clipboard: &Clipboard,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong last_shot,
+        @strong main_context,
+        @strong delay_button,
+        @strong freeze_check,
+        @strong format_drop,
+        @strong pixel_label,
+        @strong clipboard,
+        @strong error_revealer,
+        @strong error_label,
+        @weak window
+            => move |_| {
+                window.set_visible(false);
+                last_shot.set(ShotType::PickColor);
+                main_context.spawn_local(clone!(
+                    @strong delay_button,
+                    @strong freeze_check,
+                    @strong format_drop,
+                    @strong pixel_label,
+                    @strong clipboard,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @weak window
+                        => async move {
+                            handler_inner(
+                                &delay_button,
+                                &freeze_check,
+                                &format_drop,
+                                &pixel_label,
+                                &clipboard,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+    )
+}
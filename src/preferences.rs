@@ -0,0 +1,381 @@
+use std::{cell::RefCell, ffi::OsStr, path::PathBuf, rc::Rc};
+
+use anyhow::{Context, Result};
+use gtk4::{
+    gdk::RGBA,
+    gio,
+    glib::{self, clone, MainContext},
+    prelude::{Cast, EditableExt, EntryExt, FileExt, GtkWindowExt, WidgetExt},
+    traits::{BoxExt, ButtonExt},
+    Adjustment, ApplicationWindow, Box, Button, ColorDialog, ColorDialogButton, Entry,
+    EventControllerFocus, FileDialog, Label, ScrolledWindow, SpinButton, Widget, Window,
+};
+
+use crate::{
+    auto_save::{directory, pictures_directory, set_directory},
+    tools::{Tool, Tools, CONFIGURABLE},
+};
+
+/// Keyboard shortcuts of the main window, listed for reference.
+const SHORTCUTS: [(&str, &str); 8] = [
+    ("Ctrl+,", "Open preferences"),
+    ("Q, Escape", "Hide the window"),
+    ("Ctrl+Z", "Undo"),
+    ("Ctrl+Shift+Z", "Redo"),
+    ("+, =", "Zoom in"),
+    ("-", "Zoom out"),
+    ("1", "Zoom to actual size"),
+    ("0", "Zoom to fit"),
+];
+
+fn row() -> Box {
+    Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build()
+}
+
+/// Path and argument entries for one configurable helper, empty entries keep the defaults.
+#[derive(Clone)]
+pub(crate) struct ToolEntries {
+    name: &'static str,
+    path: Entry,
+    args: Entry,
+    error: Label,
+}
+
+impl ToolEntries {
+    fn new(name: &'static str) -> Self {
+        ToolEntries {
+            name,
+            path: Entry::builder()
+                .placeholder_text(name)
+                .width_chars(24)
+                .build(),
+            args: Entry::builder()
+                .placeholder_text("Extra arguments")
+                .width_chars(24)
+                .build(),
+            error: Label::builder()
+                .css_classes(["field_error"])
+                .visible(false)
+                .wrap(true)
+                .xalign(0.0)
+                .build(),
+        }
+    }
+
+    fn set(&self, tool: &Tool) {
+        let path = tool
+            .path
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let args: Vec<String> = tool
+            .args
+            .iter()
+            .map(|arg| glib::shell_quote(arg).to_string_lossy().into_owned())
+            .collect();
+        self.path.set_text(&path);
+        self.args.set_text(&args.join(" "));
+        self.error.set_visible(false);
+    }
+
+    fn read(&self) -> Result<Tool> {
+        let path = self.path.text();
+        let args = self.args.text();
+        Ok(Tool {
+            path: (!path.trim().is_empty()).then(|| PathBuf::from(path.trim())),
+            args: if args.trim().is_empty() {
+                Vec::new()
+            } else {
+                glib::shell_parse_argv(args.as_str())
+                    .with_context(|| format!("parsing the extra arguments of {}", self.name))?
+                    .iter()
+                    .map(|arg| arg.to_string_lossy().into_owned())
+                    .collect()
+            },
+        })
+    }
+
+    /// Shows what is wrong next to the entries instead of returning it.
+    fn validate(&self) -> Option<Tool> {
+        match self.read() {
+            Ok(tool) => {
+                self.error.set_visible(false);
+                Some(tool)
+            }
+            Err(e) => {
+                self.error.set_text(&format!("{e:#}"));
+                self.error.set_visible(true);
+                None
+            }
+        }
+    }
+
+    fn row(&self) -> Box {
+        let row = row();
+        row.append(&Label::new(Some(self.name)));
+        row.append(&self.path);
+        row.append(&self.args);
+        row.append(&self.error);
+        row
+    }
+}
+
+/// The backends section, kept in the `[tools]` table of the configuration file.
+///
+/// Entries are only applied when confirmed with Enter or left, so half typed paths and
+/// arguments are never run.
+#[derive(Clone)]
+pub(crate) struct BackendEntries {
+    tools: Vec<ToolEntries>,
+    /// The values of the last confirmation where every entry was valid.
+    committed: Rc<RefCell<Tools>>,
+}
+
+impl BackendEntries {
+    pub(crate) fn new() -> Self {
+        BackendEntries {
+            tools: CONFIGURABLE.into_iter().map(ToolEntries::new).collect(),
+            committed: Rc::new(RefCell::new(Tools::default())),
+        }
+    }
+
+    pub(crate) fn set(&self, tools: &Tools) {
+        for entries in &self.tools {
+            if let Some(tool) = tools.get(OsStr::new(entries.name)) {
+                entries.set(tool);
+            }
+        }
+        self.committed.replace(tools.clone());
+    }
+
+    pub(crate) fn tools(&self) -> Tools {
+        self.committed.borrow().clone()
+    }
+
+    /// Keeps the entries when all of them are valid, returns whether they were.
+    fn commit(&self) -> bool {
+        let mut tools = Tools::default();
+        let mut valid = true;
+        for entries in &self.tools {
+            match entries.validate() {
+                Some(tool) => {
+                    if let Some(slot) = tools.get_mut(entries.name) {
+                        *slot = tool;
+                    }
+                }
+                None => valid = false,
+            }
+        }
+        if valid {
+            self.committed.replace(tools);
+        }
+        valid
+    }
+
+    /// Calls `committed` when confirmed entries change the tools.
+    pub(crate) fn connect_committed(&self, committed: impl Fn() + Clone + 'static) {
+        let commit = clone!(@strong self as backends => move || {
+            let previous = backends.tools();
+            if backends.commit() && backends.tools() != previous {
+                committed();
+            }
+        });
+        for entry in self
+            .tools
+            .iter()
+            .flat_map(|entries| [&entries.path, &entries.args])
+        {
+            let activated = commit.clone();
+            entry.connect_activate(move |_| activated());
+            let focus = EventControllerFocus::new();
+            let left = commit.clone();
+            focus.connect_leave(move |_| left());
+            entry.add_controller(focus);
+        }
+    }
+
+    pub(crate) fn rows(&self) -> Vec<Widget> {
+        self.tools
+            .iter()
+            .map(|entries| entries.row().upcast())
+            .collect()
+    }
+}
+
+/// Values the editor starts with, changing them also changes the current ones.
+#[derive(Clone)]
+pub(crate) struct EditorDefaults {
+    pub(crate) color: ColorDialogButton,
+    pub(crate) stroke: SpinButton,
+    pub(crate) magnification: SpinButton,
+}
+
+impl EditorDefaults {
+    pub(crate) fn new() -> Self {
+        EditorDefaults {
+            color: ColorDialogButton::builder()
+                .dialog(&ColorDialog::new())
+                .rgba(&RGBA::RED)
+                .build(),
+            stroke: SpinButton::builder()
+                .numeric(true)
+                .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+                .wrap(false)
+                .adjustment(&Adjustment::new(4.0, 1.0, 64.0, 1.0, 4.0, 0.0))
+                .build(),
+            magnification: SpinButton::builder()
+                .numeric(true)
+                .digits(1)
+                .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+                .wrap(false)
+                .adjustment(&Adjustment::new(3.0, 1.5, 16.0, 0.5, 1.0, 0.0))
+                .build(),
+        }
+    }
+
+    pub(crate) fn connect_editor(
+        &self,
+        color_button: &ColorDialogButton,
+        stroke_button: &SpinButton,
+        magnification_button: &SpinButton,
+    ) {
+        self.color
+            .connect_rgba_notify(clone!(@weak color_button => move |defaults| {
+                color_button.set_rgba(&defaults.rgba());
+            }));
+        self.stroke
+            .connect_value_changed(clone!(@weak stroke_button => move |defaults| {
+                stroke_button.set_value(defaults.value());
+            }));
+        self.magnification.connect_value_changed(
+            clone!(@weak magnification_button => move |defaults| {
+                magnification_button.set_value(defaults.value());
+            }),
+        );
+    }
+
+    pub(crate) fn rows(&self) -> Vec<Widget> {
+        [
+            ("Colour", self.color.clone().upcast::<Widget>()),
+            ("Stroke Width", self.stroke.clone().upcast()),
+            ("Magnification", self.magnification.clone().upcast()),
+        ]
+        .into_iter()
+        .map(|(name, widget)| {
+            let row = row();
+            row.append(&Label::new(Some(name)));
+            row.append(&widget);
+            row.upcast()
+        })
+        .collect()
+    }
+}
+
+/// Where captures are saved, empty for the pictures directory.
+/// The entry is only changed through the buttons, so no partial paths get saved.
+#[derive(Clone)]
+pub(crate) struct SaveDirectory {
+    pub(crate) entry: Entry,
+}
+
+impl SaveDirectory {
+    pub(crate) fn new() -> Self {
+        let entry = Entry::builder()
+            .editable(false)
+            .hexpand(true)
+            .placeholder_text(pictures_directory().to_string_lossy().as_ref())
+            .build();
+        entry.connect_changed(|entry| set_directory(&entry.text()));
+        SaveDirectory { entry }
+    }
+
+    pub(crate) fn row(&self, main_context: &MainContext, parent: &Window) -> Widget {
+        let row = row();
+        let choose = Button::with_label("Choose Folder");
+        let reset = Button::with_label("Use Pictures");
+        row.append(&Label::new(Some("Save to")));
+        row.append(&self.entry);
+        row.append(&choose);
+        row.append(&reset);
+
+        let entry = &self.entry;
+        choose.connect_clicked(clone!(
+            @strong main_context,
+            @weak entry,
+            @weak parent
+                => move |_| {
+                    main_context.spawn_local(clone!(@weak entry, @weak parent => async move {
+                        let folder = FileDialog::builder()
+                            .initial_folder(&gio::File::for_path(directory()))
+                            .build()
+                            .select_folder_future(Some(&parent))
+                            .await;
+                        // dismissing the dialog leaves the directory unchanged
+                        if let Some(path) = folder.ok().and_then(|folder| folder.path()) {
+                            entry.set_text(&path.to_string_lossy());
+                        }
+                    }));
+                }
+        ));
+        reset.connect_clicked(clone!(@weak entry => move |_| entry.set_text("")));
+        row.upcast()
+    }
+}
+
+pub(crate) fn shortcut_rows() -> Vec<Widget> {
+    SHORTCUTS
+        .iter()
+        .map(|(keys, action)| {
+            let row = row();
+            row.append(
+                &Label::builder()
+                    .label(*keys)
+                    .width_chars(14)
+                    .xalign(0.0)
+                    .build(),
+            );
+            row.append(&Label::new(Some(*action)));
+            row.upcast()
+        })
+        .collect()
+}
+
+/// The preferences window, hidden instead of destroyed when closed.
+pub(crate) fn build_window(parent: &ApplicationWindow) -> Window {
+    Window::builder()
+        .title("Preferences")
+        .transient_for(parent)
+        .hide_on_close(true)
+        .default_width(560)
+        .default_height(640)
+        .build()
+}
+
+/// Fills the window with a heading per section followed by its rows.
+pub(crate) fn fill_window(window: &Window, sections: Vec<(&str, Vec<Widget>)>) {
+    let container = Box::builder()
+        .css_classes(["settings"])
+        .orientation(gtk4::Orientation::Vertical)
+        .build();
+    for (title, rows) in sections {
+        container.append(
+            &Label::builder()
+                .label(title)
+                .css_classes(["heading"])
+                .xalign(0.0)
+                .build(),
+        );
+        for row in rows {
+            container.append(&row);
+        }
+    }
+    window.set_child(Some(
+        &ScrolledWindow::builder()
+            .hscrollbar_policy(gtk4::PolicyType::Never)
+            .child(&container)
+            .build(),
+    ));
+}
@@ -0,0 +1,411 @@
+use std::{
+    cell::{Cell, RefCell},
+    ffi::OsStr,
+    fs::metadata,
+    rc::Rc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gdk::{Clipboard, ContentProvider, FileList, Monitor},
+    gio::{self, File, InputStream, ListStore, Subprocess, SubprocessFlags},
+    glib::{
+        self, clone, future_with_timeout, timeout_future_seconds, Bytes, JoinHandle, MainContext,
+        Priority, ToValue,
+    },
+    prelude::{
+        CastNone, DisplayExt, FileExt, InputStreamExt, ListModelExt, MediaStreamExt, MonitorExt,
+        NativeExt, WidgetExt,
+    },
+    ApplicationWindow, Button, DropDown, FileDialog, FileFilter, Label, MediaFile, Picture,
+    Revealer, SpinButton,
+};
+
+use crate::{
+    auto_save::TempFile,
+    command::{select_region, spawn_context},
+    editor::Document,
+    ShotType,
+};
+
+/// wf-recorder finishes writing the file when interrupted.
+const SIGINT: i32 = 2;
+/// How much of wf-recorder's error output is kept to explain a failed recording.
+const STDERR_TAIL: usize = 4096;
+/// wf-recorder exits within this time when it can't open the output or the encoder.
+const STARTUP_CHECK: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VideoFormat {
+    Mp4,
+    WebM,
+}
+
+impl VideoFormat {
+    pub(crate) const NAMES: [&'static str; 2] = ["MP4", "WebM"];
+
+    pub(crate) fn from_selected(format_drop: &DropDown) -> Self {
+        match format_drop.selected() {
+            1 => VideoFormat::WebM,
+            _ => VideoFormat::Mp4,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::WebM => "webm",
+        }
+    }
+
+    /// Extra wf-recorder arguments, the container is chosen from the file extension.
+    fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Mp4 => &[],
+            VideoFormat::WebM => &["-c", "libvpx-vp9"],
+        }
+    }
+}
+
+pub(crate) struct Recorder {
+    process: Subprocess,
+    file: TempFile,
+    /// Reads the error output while recording so the pipe never fills up and blocks wf-recorder.
+    stderr: JoinHandle<Vec<u8>>,
+}
+
+/// State shared between the recording buttons, the activation socket and the result panel.
+#[derive(Clone)]
+pub(crate) struct RecordingControls {
+    pub(crate) recorder: Rc<RefCell<Option<Recorder>>>,
+    /// The shown recording or animation, its file is removed when it is replaced.
+    pub(crate) recorded: Rc<RefCell<Option<TempFile>>>,
+    pub(crate) format_drop: DropDown,
+    pub(crate) revealer: Revealer,
+}
+
+/// Reads the stream to the end and returns the last `STDERR_TAIL` bytes.
+async fn drain(stream: InputStream) -> Vec<u8> {
+    let mut tail = Vec::new();
+    while let Ok(bytes) = stream
+        .read_bytes_future(STDERR_TAIL, Priority::DEFAULT)
+        .await
+    {
+        if bytes.is_empty() {
+            break;
+        }
+        tail.extend_from_slice(&bytes);
+        let excess = tail.len().saturating_sub(STDERR_TAIL);
+        tail.drain(..excess);
+    }
+    tail
+}
+
+/// wf-recorder records a single output, full screen recordings use the monitor the window is
+/// shown on, or the first one while it is hidden.
+fn screen_output(window: &ApplicationWindow) -> Result<String> {
+    let display = WidgetExt::display(window);
+    let monitor = display
+        .monitor_at_surface(&window.surface())
+        .or_else(|| display.monitors().item(0).and_downcast::<Monitor>())
+        .context("finding the monitor to record")?;
+    Ok(monitor
+        .connector()
+        .context("getting the output name of the monitor")?
+        .to_string())
+}
+
+/// Records `output`, or a region selected with slurp without one.
+async fn start(output: Option<String>, format: VideoFormat, wait_seconds: u32) -> Result<Recorder> {
+    timeout_future_seconds(wait_seconds).await;
+
+    let (flag, area) = match output {
+        Some(output) => ("-o", output),
+        None => ("-g", select_region().await?),
+    };
+
+    let file = TempFile::new(format.extension())?;
+    let mut argv = vec![
+        OsStr::new("wf-recorder"),
+        OsStr::new("-y"),
+        OsStr::new(flag),
+        OsStr::new(&area),
+        OsStr::new("-f"),
+        file.path().as_os_str(),
+    ];
+    argv.extend(format.codec_args().iter().map(OsStr::new));
+    let process = Subprocess::newv(
+        &argv,
+        SubprocessFlags::STDOUT_SILENCE | SubprocessFlags::STDERR_PIPE,
+    )
+    .with_context(|| spawn_context(argv[0]))?;
+    let stderr =
+        MainContext::default().spawn_local(drain(process.stderr_pipe().expect("stderr pipe")));
+    // report a failed start right away instead of on the next toggle
+    if let Ok(exited) = future_with_timeout(STARTUP_CHECK, process.wait_future()).await {
+        exited.context("waiting for wf-recorder")?;
+        let err = stderr.await.unwrap_or_default();
+        return Err(anyhow!(
+            "wf-recorder exited with status {} right after starting:\n{}",
+            process.exit_status(),
+            String::from_utf8_lossy(&err)
+        ));
+    }
+    Ok(Recorder {
+        process,
+        file,
+        stderr,
+    })
+}
+
+async fn stop(recorder: Recorder) -> Result<TempFile> {
+    recorder.process.send_signal(SIGINT);
+    recorder
+        .process
+        .wait_future()
+        .await
+        .context("waiting for wf-recorder to finish")?;
+    // the file is created empty before wf-recorder overwrites it
+    if metadata(recorder.file.path()).map_or(true, |metadata| metadata.len() == 0) {
+        let err = recorder.stderr.await.unwrap_or_default();
+        return Err(anyhow!(
+            "wf-recorder exited with status {} without writing a recording:\n{}",
+            recorder.process.exit_status(),
+            String::from_utf8_lossy(&err)
+        ));
+    }
+    Ok(recorder.file)
+}
+
+fn show_recording(
+    file: TempFile,
+    controls: &RecordingControls,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+) {
+    let media = MediaFile::for_file(&File::for_path(file.path()));
+    media.set_loop(true);
+    media.play();
+    // the preview no longer shows a screenshot that could be edited or saved
+    image.replace(None);
+    image_view.set_paintable(Some(&media));
+    controls.recorded.replace(Some(file));
+    controls.revealer.set_reveal_child(true);
+    image_revealer.set_reveal_child(true);
+}
+
+/// Starts a recording, or stops the running one and shows it, so the same shortcut toggles it.
+pub(crate) async fn handler_inner(
+    selection: bool,
+    controls: &RecordingControls,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    delay_button: &SpinButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) {
+    let running = controls.recorder.take();
+    let result = match running {
+        Some(recorder) => stop(recorder).await.map(|file| {
+            show_recording(file, controls, image, image_view, image_revealer);
+            true
+        }),
+        None => {
+            // looked up while the window is still shown
+            let output = (!selection).then(|| screen_output(window)).transpose();
+            window.set_visible(false);
+            let format = VideoFormat::from_selected(&controls.format_drop);
+            match output {
+                Ok(output) => start(output, format, delay_button.value() as u32)
+                    .await
+                    .map(|recorder| {
+                        controls.recorder.replace(Some(recorder));
+                        false
+                    }),
+                Err(e) => Err(e),
+            }
+        }
+    };
+    match result {
+        // keep the window out of the recording
+        Ok(false) => {}
+        Ok(true) => {
+            error_revealer.set_reveal_child(false);
+            window.set_visible(true);
+        }
+        Err(e) => {
+            error_label.set_text(&format!("{:?}", e));
+            error_revealer.set_reveal_child(true);
+            window.set_visible(true);
+        }
+    }
+}
+
+pub(crate) fn get_handler(
+    selection: bool,
+    last_shot: &Rc<Cell<ShotType>>,
+    main_context: &MainContext,
+    controls: &RecordingControls,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    delay_button: &SpinButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong last_shot,
+        @strong main_context,
+        @strong controls,
+        @strong image,
+        @strong image_view,
+        @strong image_revealer,
+        @strong delay_button,
+        @strong error_revealer,
+        @strong error_label,
+        @weak window
+            => move |_| {
+                last_shot.set(ShotType::Recording { selection });
+                main_context.spawn_local(clone!(
+                    @strong controls,
+                    @strong image,
+                    @strong image_view,
+                    @strong image_revealer,
+                    @strong delay_button,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @weak window
+                        => async move {
+                            handler_inner(
+                                selection,
+                                &controls,
+                                &image,
+                                &image_view,
+                                &image_revealer,
+                                &delay_button,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+    )
+}
+
+async fn save_recording(
+    window: &ApplicationWindow,
+    recorded: &Rc<RefCell<Option<TempFile>>>,
+) -> Result<File> {
+    let source = recorded
+        .borrow()
+        .as_ref()
+        .map(|recording| recording.path().to_path_buf())
+        .ok_or_else(|| anyhow!("No recording available to save"))?;
+    let extension = source
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_string();
+    let filter = FileFilter::new();
+    filter.add_suffix(&extension);
+    let filters = ListStore::new::<FileFilter>();
+    filters.append(&filter);
+    let output = FileDialog::builder()
+        .default_filter(&filter)
+        .filters(&filters)
+        .initial_name(format!("recording.{extension}"))
+        .build()
+        .save_future(Some(window))
+        .await
+        .context("choosing output file")?;
+    let destination = output
+        .path()
+        .ok_or_else(|| anyhow!("output file has no local path"))?;
+    gio::spawn_blocking(move || std::fs::copy(source, destination))
+        .await
+        .map_err(|_| anyhow!("copying recording panicked"))?
+        .context("writing recording to file")?;
+    Ok(output)
+}
+
+pub(crate) fn get_save_handler(
+    main_context: &MainContext,
+    window: &ApplicationWindow,
+    controls: &RecordingControls,
+    saved_file: &Rc<RefCell<Option<File>>>,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong main_context,
+        @weak window,
+        @strong controls,
+        @strong saved_file,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                main_context.spawn_local(clone!(
+                    @weak window,
+                    @strong controls,
+                    @strong saved_file,
+                    @strong error_revealer,
+                    @strong error_label
+                        => async move {
+                            match save_recording(&window, &controls.recorded).await {
+                                Ok(output) => {
+                                    saved_file.replace(Some(output));
+                                }
+                                Err(e) => {
+                                    error_label.set_text(&format!("{e:?}"));
+                                    error_revealer.set_reveal_child(true);
+                                }
+                            }
+                        }
+                ));
+            }
+    )
+}
+
+fn copy_recording(recorded: &Rc<RefCell<Option<TempFile>>>, clipboard: &Clipboard) -> Result<()> {
+    let recording = recorded
+        .borrow()
+        .as_ref()
+        .map(|recording| File::for_path(recording.path()))
+        .ok_or_else(|| anyhow!("No recording available to copy"))?;
+    clipboard
+        .set_content(Some(&ContentProvider::new_union(&[
+            ContentProvider::for_bytes(
+                "text/uri-list",
+                &Bytes::from_owned(format!("{}\r\n", recording.uri())),
+            ),
+            ContentProvider::for_value(&FileList::from_array(&[recording]).to_value()),
+        ])))
+        .context("Saving Recording to Clipboard")
+}
+
+pub(crate) fn get_copy_handler(
+    controls: &RecordingControls,
+    clipboard: &Clipboard,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong controls,
+        @strong clipboard,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                if let Err(e) = copy_recording(&controls.recorded, &clipboard) {
+                    error_label.set_text(&format!("{e:?}"));
+                    error_revealer.set_reveal_child(true);
+                }
+            }
+    )
+}
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use gtk4::cairo::{Context as DrawingContext, ImageSurface};
+
+use crate::editor::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Redaction {
+    Pixelate,
+    Blur,
+    Blackout,
+}
+
+struct Region {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Region {
+    fn clamped(start: Point, end: Point, surface: &ImageSurface) -> Option<Self> {
+        let clamp_x = |x: f64| x.round().clamp(0.0, surface.width() as f64) as usize;
+        let clamp_y = |y: f64| y.round().clamp(0.0, surface.height() as f64) as usize;
+        let (x0, x1) = (clamp_x(start.0.min(end.0)), clamp_x(start.0.max(end.0)));
+        let (y0, y1) = (clamp_y(start.1.min(end.1)), clamp_y(start.1.max(end.1)));
+        (x1 > x0 && y1 > y0).then_some(Region {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+        })
+    }
+}
+
+pub(crate) fn redact(
+    surface: &mut ImageSurface,
+    start: Point,
+    end: Point,
+    kind: Redaction,
+    size: usize,
+) -> Result<()> {
+    let Some(region) = Region::clamped(start, end, surface) else {
+        return Ok(());
+    };
+    let stride = surface.stride() as usize;
+    match kind {
+        Redaction::Pixelate => pixelate(
+            &mut surface.data().context("accessing image surface")?,
+            stride,
+            &region,
+            size.max(2),
+        ),
+        Redaction::Blur => {
+            let mut data = surface.data().context("accessing image surface")?;
+            for _ in 0..3 {
+                box_blur(&mut data, stride, &region, size.max(1));
+            }
+        }
+        Redaction::Blackout => {
+            let cr = DrawingContext::new(&*surface).context("creating drawing context")?;
+            cr.set_source_rgb(0.0, 0.0, 0.0);
+            cr.rectangle(
+                region.x as f64,
+                region.y as f64,
+                region.width as f64,
+                region.height as f64,
+            );
+            cr.fill().context("filling redacted region")?;
+        }
+    }
+    Ok(())
+}
+
+fn fill(data: &mut [u8], stride: usize, region: &Region, pixel: [u8; 4]) {
+    for y in region.y..region.y + region.height {
+        for x in region.x..region.x + region.width {
+            let offset = y * stride + x * 4;
+            data[offset..offset + 4].copy_from_slice(&pixel);
+        }
+    }
+}
+
+fn pixelate(data: &mut [u8], stride: usize, region: &Region, block: usize) {
+    for block_y in (region.y..region.y + region.height).step_by(block) {
+        for block_x in (region.x..region.x + region.width).step_by(block) {
+            let block_region = Region {
+                x: block_x,
+                y: block_y,
+                width: block.min(region.x + region.width - block_x),
+                height: block.min(region.y + region.height - block_y),
+            };
+            let mut sum = [0usize; 4];
+            for y in block_y..block_y + block_region.height {
+                for x in block_x..block_x + block_region.width {
+                    let offset = y * stride + x * 4;
+                    for (channel, value) in sum.iter_mut().zip(&data[offset..offset + 4]) {
+                        *channel += *value as usize;
+                    }
+                }
+            }
+            let count = block_region.width * block_region.height;
+            let average = sum.map(|channel| (channel / count) as u8);
+            fill(data, stride, &block_region, average);
+        }
+    }
+}
+
+/// Averages every pixel with its neighbours within `radius`, first along rows, then along columns.
+/// Repeated passes approximate a gaussian blur.
+fn box_blur(data: &mut [u8], stride: usize, region: &Region, radius: usize) {
+    let mut line = Vec::new();
+    for y in region.y..region.y + region.height {
+        line.clear();
+        line.extend((region.x..region.x + region.width).map(|x| y * stride + x * 4));
+        blur_line(data, &line, radius);
+    }
+    for x in region.x..region.x + region.width {
+        line.clear();
+        line.extend((region.y..region.y + region.height).map(|y| y * stride + x * 4));
+        blur_line(data, &line, radius);
+    }
+}
+
+fn blur_line(data: &mut [u8], offsets: &[usize], radius: usize) {
+    let pixels: Vec<[u8; 4]> = offsets
+        .iter()
+        .map(|&offset| {
+            [
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ]
+        })
+        .collect();
+    let len = pixels.len();
+    let mut sum = [0usize; 4];
+    let mut count = 0;
+    for pixel in pixels.iter().take(radius.min(len)) {
+        for (channel, value) in sum.iter_mut().zip(pixel) {
+            *channel += *value as usize;
+        }
+        count += 1;
+    }
+    for (index, &offset) in offsets.iter().enumerate() {
+        if index + radius < len {
+            for (channel, value) in sum.iter_mut().zip(&pixels[index + radius]) {
+                *channel += *value as usize;
+            }
+            count += 1;
+        }
+        if index > radius {
+            for (channel, value) in sum.iter_mut().zip(&pixels[index - radius - 1]) {
+                *channel -= *value as usize;
+            }
+            count -= 1;
+        }
+        for (target, channel) in data[offset..offset + 4].iter_mut().zip(sum) {
+            *target = (channel / count) as u8;
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    glib::{self, clone},
+    prelude::{BoxExt, ButtonExt, CheckButtonExt, GtkWindowExt, IsA, TextureExt},
+    Adjustment, ApplicationWindow, Box, Button, CheckButton, Label, Picture, Revealer, SpinButton,
+    Widget, Window,
+};
+
+use crate::editor::{show_texture, Document, Operation};
+
+fn size_button(value: f64, upper: f64) -> SpinButton {
+    SpinButton::builder()
+        .numeric(true)
+        .update_policy(gtk4::SpinButtonUpdatePolicy::IfValid)
+        .wrap(false)
+        .adjustment(&Adjustment::new(value, 1.0, upper, 1.0, 10.0, 0.0))
+        .build()
+}
+
+fn setting_row(label: &str, widget: &impl IsA<Widget>) -> Box {
+    let row = Box::builder()
+        .css_classes(["setting"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    row.append(&Label::new(Some(label)));
+    row.append(widget);
+    row
+}
+
+fn apply(
+    width: i32,
+    height: i32,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+) -> Result<()> {
+    let mut document = image.borrow_mut();
+    let document = document
+        .as_mut()
+        .ok_or_else(|| anyhow!("No screenshot available to resize"))?;
+    if (width, height) != (document.texture().width(), document.texture().height()) {
+        document
+            .push(Operation::Resize { width, height })
+            .context("applying resize")?;
+        show_texture(image_view, document.texture());
+    }
+    Ok(())
+}
+
+fn show_dialog(
+    window: &ApplicationWindow,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> Result<()> {
+    let (width, height) = image
+        .borrow()
+        .as_ref()
+        .map(|document| {
+            (
+                document.texture().width() as f64,
+                document.texture().height() as f64,
+            )
+        })
+        .ok_or_else(|| anyhow!("No screenshot available to resize"))?;
+
+    let dialog = Window::builder()
+        .title("Resize")
+        .transient_for(window)
+        .modal(true)
+        .build();
+    let content = Box::builder()
+        .css_classes(["main"])
+        .orientation(gtk4::Orientation::Vertical)
+        .build();
+    let width_button = size_button(width, 16384.0);
+    let height_button = size_button(height, 16384.0);
+    let scale_button = size_button(100.0, 1000.0);
+    let keep_aspect = CheckButton::builder().active(true).build();
+    let buttons = Box::builder()
+        .css_classes(["setting", "buttons"])
+        .orientation(gtk4::Orientation::Horizontal)
+        .build();
+    let cancel = Button::with_label("Cancel");
+    let resize = Button::with_label("Resize");
+
+    let updating = Rc::new(Cell::new(false));
+    width_button.connect_value_changed(clone!(
+        @strong updating,
+        @weak height_button,
+        @weak scale_button,
+        @weak keep_aspect
+            => move |width_button| {
+                if updating.replace(true) {
+                    return;
+                }
+                if keep_aspect.is_active() {
+                    height_button.set_value((width_button.value() * height / width).round());
+                    scale_button.set_value((width_button.value() * 100.0 / width).round());
+                }
+                updating.set(false);
+            }
+    ));
+    height_button.connect_value_changed(clone!(
+        @strong updating,
+        @weak width_button,
+        @weak scale_button,
+        @weak keep_aspect
+            => move |height_button| {
+                if updating.replace(true) {
+                    return;
+                }
+                if keep_aspect.is_active() {
+                    width_button.set_value((height_button.value() * width / height).round());
+                    scale_button.set_value((height_button.value() * 100.0 / height).round());
+                }
+                updating.set(false);
+            }
+    ));
+    scale_button.connect_value_changed(clone!(
+        @strong updating,
+        @weak width_button,
+        @weak height_button
+            => move |scale_button| {
+                if updating.replace(true) {
+                    return;
+                }
+                width_button.set_value((width * scale_button.value() / 100.0).round());
+                height_button.set_value((height * scale_button.value() / 100.0).round());
+                updating.set(false);
+            }
+    ));
+    cancel.connect_clicked(clone!(@weak dialog => move |_| dialog.close()));
+    resize.connect_clicked(clone!(
+        @weak dialog,
+        @strong image,
+        @weak image_view,
+        @weak width_button,
+        @weak height_button,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                if let Err(e) = apply(
+                    width_button.value() as i32,
+                    height_button.value() as i32,
+                    &image,
+                    &image_view,
+                ) {
+                    error_label.set_text(&format!("{e:?}"));
+                    error_revealer.set_reveal_child(true);
+                }
+                dialog.close();
+            }
+    ));
+
+    content.append(&setting_row("Width", &width_button));
+    content.append(&setting_row("Height", &height_button));
+    content.append(&setting_row("Scale (%)", &scale_button));
+    content.append(&setting_row("Keep Aspect Ratio", &keep_aspect));
+    buttons.append(&cancel);
+    buttons.append(&resize);
+    content.append(&buttons);
+    dialog.set_child(Some(&content));
+    dialog.present();
+    Ok(())
+}
+
+pub(crate) fn get_handler(
+    window: &ApplicationWindow,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @weak window,
+        @strong image,
+        @strong image_view,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                if let Err(e) = show_dialog(&window, &image, &image_view, &error_revealer, &error_label) {
+                    error_label.set_text(&format!("{e:?}"));
+                    error_revealer.set_reveal_child(true);
+                }
+            }
+    )
+}
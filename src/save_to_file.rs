@@ -4,29 +4,42 @@ use std::{
 };
 
 use gtk4::{
-    gio::{FileCreateFlags, ListStore},
-    glib::{self, clone, Bytes, MainContext, Priority},
+    gdk::Clipboard,
+    gio::{File, FileCreateFlags, ListStore},
+    glib::{self, clone, MainContext, Priority},
     prelude::{FileExt, OutputStreamExtManual},
-    ApplicationWindow, Button, FileDialog, FileFilter, Label, Revealer,
+    traits::CheckButtonExt,
+    ApplicationWindow, Button, CheckButton, DropDown, FileDialog, FileFilter, Label, Revealer,
 };
 
 use anyhow::{anyhow, Context, Result};
 
-async fn save_to_file(window: ApplicationWindow, file: Rc<RefCell<Option<Bytes>>>) -> Result<()> {
+use crate::{
+    auto_save::directory,
+    copy_path::{copy_path, PathFormat},
+    editor::Document,
+};
+
+async fn save_to_file(
+    window: ApplicationWindow,
+    file: Rc<RefCell<Option<Document>>>,
+) -> Result<File> {
     let file = Ref::filter_map(file.borrow(), Option::as_ref)
         .map_err(|_| anyhow!("No screenshot available to save"))?
-        .clone();
+        .bytes();
     let filter = FileFilter::new();
     filter.add_suffix("png");
     let filters = ListStore::new::<FileFilter>();
     filters.append(&filter);
-    FileDialog::builder()
+    let output = FileDialog::builder()
         .default_filter(&filter)
         .filters(&filters)
+        .initial_folder(&File::for_path(directory()))
         .build()
         .save_future(Some(&window))
         .await
-        .context("choosing output file")?
+        .context("choosing output file")?;
+    output
         .create_future(FileCreateFlags::REPLACE_DESTINATION, Priority::DEFAULT)
         .await
         .context("creating output file")?
@@ -34,29 +47,64 @@ async fn save_to_file(window: ApplicationWindow, file: Rc<RefCell<Option<Bytes>>
         .await
         .map_err(|(_, e)| e)
         .context("writing image to file")?;
-    Ok(())
+    Ok(output)
 }
 fn handler(
     main_context: &MainContext,
     window: &ApplicationWindow,
-    file: &Rc<RefCell<Option<Bytes>>>,
+    file: &Rc<RefCell<Option<Document>>>,
+    saved_file: &Rc<RefCell<Option<File>>>,
+    copy_after_save: &CheckButton,
+    path_format: &DropDown,
+    clipboard: &Clipboard,
     error_revealer: &Revealer,
     error_label: &Label,
 ) {
-    main_context.spawn_local(clone!(@strong file, @weak window, @strong error_revealer, @strong error_label => async move{
-                if let Err(e) = save_to_file(window, file).await{
-                    error_label.set_text(&format!("{e:?}"));
-                    error_revealer.set_reveal_child(true);
+    main_context.spawn_local(clone!(
+    @strong file,
+    @strong saved_file,
+    @strong copy_after_save,
+    @strong path_format,
+    @strong clipboard,
+    @weak window,
+    @strong error_revealer,
+    @strong error_label
+        => async move{
+            let result = save_to_file(window, file).await.and_then(|output| {
+                saved_file.replace(Some(output.clone()));
+                if copy_after_save.is_active() {
+                    copy_path(&output, PathFormat::from_selected(&path_format), &clipboard)
+                } else {
+                    Ok(())
                 }
-            }));
+            });
+            if let Err(e) = result {
+                error_label.set_text(&format!("{e:?}"));
+                error_revealer.set_reveal_child(true);
+            }
+        }));
 }
 
 pub fn get_handler(
     main_context: &MainContext,
     window: &ApplicationWindow,
-    file: &Rc<RefCell<Option<Bytes>>>,
+    file: &Rc<RefCell<Option<Document>>>,
+    saved_file: &Rc<RefCell<Option<File>>>,
+    copy_after_save: &CheckButton,
+    path_format: &DropDown,
+    clipboard: &Clipboard,
     error_revealer: &Revealer,
     error_label: &Label,
 ) -> impl Fn(&Button) {
-    clone!(@strong file, @weak window, @strong error_revealer, @strong error_label, @strong main_context => move |_| handler(&main_context, &window, &file, &error_revealer, &error_label))
+    clone!(
+        @strong file,
+        @strong saved_file,
+        @strong copy_after_save,
+        @strong path_format,
+        @strong clipboard,
+        @weak window,
+        @strong error_revealer,
+        @strong error_label,
+        @strong main_context
+            => move |_| handler(&main_context, &window, &file, &saved_file, &copy_after_save, &path_format, &clipboard, &error_revealer, &error_label))
 }
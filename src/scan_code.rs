@@ -0,0 +1,342 @@
+use std::{
+    cell::{Cell, RefCell},
+    ffi::OsStr,
+    fs::{remove_file, File},
+    io::Write,
+    os::fd::FromRawFd,
+    rc::Rc,
+    str::from_utf8,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    gdk::Clipboard,
+    gio::{Cancellable, Subprocess, SubprocessFlags},
+    glib::{self, clone, Bytes, MainContext, Uri},
+    prelude::{BoxExt, ButtonExt, CheckButtonExt, WidgetExt},
+    ApplicationWindow, Button, CheckButton, Label, Revealer, SpinButton, UriLauncher,
+};
+
+use crate::{command::spawn_context, editor::Document, snap_selection::snap_selection, ShotType};
+
+/// Symbol types zbarimg prefixes its results with.
+const SYMBOLOGIES: [&str; 18] = [
+    "QR-Code",
+    "SQ-Code",
+    "EAN-2",
+    "EAN-5",
+    "EAN-8",
+    "EAN-13",
+    "UPC-A",
+    "UPC-E",
+    "ISBN-10",
+    "ISBN-13",
+    "I2/5",
+    "DataBar",
+    "DataBar-Exp",
+    "Codabar",
+    "CODE-39",
+    "CODE-93",
+    "CODE-128",
+    "PDF417",
+];
+/// zbarimg exits with this status when the image contains no codes.
+const NOTHING_FOUND: i32 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Code {
+    symbology: String,
+    data: String,
+}
+
+/// Splits zbarimg output into codes, lines without a known prefix continue the previous code.
+fn parse_codes(output: &str) -> Vec<Code> {
+    let mut codes: Vec<Code> = Vec::new();
+    for line in output.lines() {
+        let symbol = line
+            .split_once(':')
+            .filter(|(symbology, _)| SYMBOLOGIES.contains(symbology));
+        match (symbol, codes.last_mut()) {
+            (Some((symbology, data)), _) => codes.push(Code {
+                symbology: symbology.to_string(),
+                data: data.to_string(),
+            }),
+            (None, Some(code)) => {
+                code.data.push('\n');
+                code.data.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+    codes
+}
+
+/// Decodes all codes in a PNG image with zbarimg, which runs entirely offline.
+async fn scan(png: &Bytes) -> Result<Vec<Code>> {
+    let (fd, path) =
+        glib::file_open_tmp(Some("shots-scan-XXXXXX.png")).context("creating temporary file")?;
+    // SAFETY: the descriptor was just opened for us and is not used elsewhere
+    let written = unsafe { File::from_raw_fd(fd) }.write_all(png);
+    if let Err(e) = written {
+        let _ = remove_file(&path);
+        return Err(e).context("writing temporary file");
+    }
+    let zbarimg = Subprocess::newv(
+        &[
+            OsStr::new("zbarimg"),
+            OsStr::new("--quiet"),
+            path.as_os_str(),
+        ],
+        SubprocessFlags::STDOUT_PIPE | SubprocessFlags::STDERR_PIPE,
+    )
+    .with_context(|| spawn_context(OsStr::new("zbarimg")));
+    let output = match zbarimg {
+        Ok(zbarimg) => zbarimg
+            .communicate_future(None)
+            .await
+            .context("receiving output from zbarimg")
+            .map(|output| (zbarimg, output)),
+        Err(e) => Err(e),
+    };
+    let _ = remove_file(&path);
+    let (zbarimg, (out, err)) = output?;
+
+    if zbarimg.is_successful() {
+        let out = out.expect("stdout output");
+        Ok(parse_codes(
+            from_utf8(&out).context("decoding zbarimg output")?,
+        ))
+    } else if zbarimg.exit_status() == NOTHING_FOUND {
+        Err(anyhow!("No QR code or barcode found"))
+    } else {
+        let err = err.expect("stderr output");
+        if err.is_empty() {
+            Err(anyhow!(
+                "zbarimg failed with exit status {} but no error output was provided",
+                zbarimg.exit_status()
+            ))
+        } else {
+            Err(anyhow!(
+                "zbarimg failed with exit status {}:\n{}",
+                zbarimg.exit_status(),
+                from_utf8(&err).context("decoding zbarimg stderr output")?
+            ))
+        }
+    }
+}
+
+/// Lists decoded codes with buttons to copy them or open them when they are links.
+#[derive(Clone)]
+pub(crate) struct CodePanel {
+    pub(crate) revealer: Revealer,
+    pub(crate) list: gtk4::Box,
+    pub(crate) clipboard: Clipboard,
+    pub(crate) window: ApplicationWindow,
+}
+
+impl CodePanel {
+    fn show(&self, codes: &[Code]) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+        let (clipboard, window) = (&self.clipboard, &self.window);
+        for code in codes {
+            let row = gtk4::Box::builder()
+                .css_classes(["setting"])
+                .orientation(gtk4::Orientation::Horizontal)
+                .build();
+            let label = Label::builder()
+                .label(format!("{}: {}", code.symbology, code.data))
+                .selectable(true)
+                .wrap(true)
+                .max_width_chars(40)
+                .build();
+            let data = code.data.clone();
+            let copy = Button::with_label("Copy");
+            copy.connect_clicked(clone!(
+                @strong clipboard,
+                @strong data
+                    => move |_| clipboard.set_text(&data)
+            ));
+            row.append(&label);
+            row.append(&copy);
+            if Uri::peek_scheme(&code.data).is_some() {
+                let open = Button::with_label("Open");
+                open.connect_clicked(clone!(
+                    @weak window,
+                    @strong data
+                        => move |_| {
+                            UriLauncher::new(&data).launch(
+                                Some(&window),
+                                Cancellable::NONE,
+                                |_| {},
+                            )
+                        }
+                ));
+                row.append(&open);
+            }
+            self.list.append(&row);
+        }
+        self.revealer.set_reveal_child(true);
+    }
+}
+
+async fn show_codes(png: Result<Bytes>, panel: &CodePanel) -> Result<()> {
+    let codes = scan(&png?).await?;
+    panel.show(&codes);
+    Ok(())
+}
+
+/// Scans the current screenshot.
+pub(crate) fn get_handler(
+    main_context: &MainContext,
+    image: &Rc<RefCell<Option<Document>>>,
+    panel: &CodePanel,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong main_context,
+        @strong image,
+        @strong panel,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                let png = image
+                    .borrow()
+                    .as_ref()
+                    .map(Document::bytes)
+                    .ok_or_else(|| anyhow!("No screenshot available to scan"));
+                main_context.spawn_local(clone!(
+                    @strong panel,
+                    @strong error_revealer,
+                    @strong error_label
+                        => async move {
+                            if let Err(e) = show_codes(png, &panel).await {
+                                error_label.set_text(&format!("{e:?}"));
+                                error_revealer.set_reveal_child(true);
+                            }
+                        }
+                ));
+            }
+    )
+}
+
+/// Captures a selection and only keeps the codes decoded from it.
+pub(crate) async fn handler_inner(
+    panel: &CodePanel,
+    delay_button: &SpinButton,
+    freeze_check: &CheckButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) {
+    let png = snap_selection(false, freeze_check.is_active(), delay_button.value() as u32).await;
+    match show_codes(png, panel).await {
+        Ok(()) => error_revealer.set_reveal_child(false),
+        Err(e) => {
+            error_label.set_text(&format!("{:?}", e));
+            error_revealer.set_reveal_child(true);
+        }
+    }
+    window.set_visible(true);
+}
+
+pub(crate) fn get_capture_handler(
+    last_shot: &Rc<Cell<ShotType>>,
+    main_context: &MainContext,
+    panel: &CodePanel,
+    delay_button: &SpinButton,
+    freeze_check: &CheckButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong last_shot,
+        @strong main_context,
+        @strong panel,
+        @strong delay_button,
+        @strong freeze_check,
+        @strong error_revealer,
+        @strong error_label,
+        @weak window
+            => move |_| {
+                window.set_visible(false);
+                last_shot.set(ShotType::Code);
+                main_context.spawn_local(clone!(
+                    @strong panel,
+                    @strong delay_button,
+                    @strong freeze_check,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @weak window
+                        => async move {
+                            handler_inner(
+                                &panel,
+                                &delay_button,
+                                &freeze_check,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(symbology: &str, data: &str) -> Code {
+        Code {
+            symbology: symbology.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_codes_splits_symbols() {
+        let codes = parse_codes("QR-Code:hello\nEAN-13:4006381333931\nCODE-128:ABC-123\n");
+        assert_eq!(
+            codes,
+            [
+                code("QR-Code", "hello"),
+                code("EAN-13", "4006381333931"),
+                code("CODE-128", "ABC-123"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_codes_keeps_colons_in_payload() {
+        let codes = parse_codes("QR-Code:https://example.com:8080/a?b=c:d\n");
+        assert_eq!(codes, [code("QR-Code", "https://example.com:8080/a?b=c:d")]);
+    }
+
+    #[test]
+    fn parse_codes_joins_continuation_lines() {
+        let codes = parse_codes(
+            "QR-Code:BEGIN:VCARD\nFN:Jane Doe\nTEL:+1 555 0100\nEND:VCARD\nI2/5:0123456789\n",
+        );
+        assert_eq!(
+            codes,
+            [
+                code(
+                    "QR-Code",
+                    "BEGIN:VCARD\nFN:Jane Doe\nTEL:+1 555 0100\nEND:VCARD"
+                ),
+                code("I2/5", "0123456789"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_codes_ignores_unprefixed_output() {
+        assert!(parse_codes("").is_empty());
+        assert!(parse_codes("scanned 1 barcode symbols\n").is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::hash_map::DefaultHasher,
+    ffi::OsStr,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{
+    cairo::{Format, ImageSurface},
+    gdk::Texture,
+    glib::{self, clone, timeout_future, Bytes, MainContext},
+    prelude::{TextureExt, WidgetExt},
+    ApplicationWindow, Button, Label, Picture, Revealer, SpinButton,
+};
+
+use crate::{
+    command::{run, select_region},
+    editor::{show_texture, surface_from_texture, texture_from_surface, Document},
+    ShotType,
+};
+
+const INTERVAL: Duration = Duration::from_millis(400);
+const MAX_FRAMES: usize = 200;
+/// The capture ends once the region has not changed for this many captures in a row.
+const STILL_FRAMES: usize = 5;
+/// Shorter matches are too likely to be blank lines lining up by chance.
+const MIN_OVERLAP: usize = 16;
+
+/// One capture as ARGB32 rows, with a hash per row to find overlaps quickly.
+struct Frame {
+    width: i32,
+    stride: usize,
+    data: Vec<u8>,
+    hashes: Vec<u64>,
+}
+
+impl Frame {
+    fn new(texture: &Texture) -> Result<Self> {
+        let mut surface = surface_from_texture(texture)?;
+        let (width, stride) = (surface.width(), surface.stride() as usize);
+        let data = surface.data().context("accessing frame")?.to_vec();
+        Ok(Frame::from_data(width, stride, data))
+    }
+
+    fn from_data(width: i32, stride: usize, data: Vec<u8>) -> Self {
+        let row_bytes = width as usize * 4;
+        let hashes = data
+            .chunks(stride)
+            .map(|row| {
+                let mut hasher = DefaultHasher::new();
+                row[..row_bytes].hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect();
+        Frame {
+            width,
+            stride,
+            data,
+            hashes,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.hashes.len()
+    }
+
+    fn rows(&self, from: usize, to: usize) -> &[u8] {
+        &self.data[from * self.stride..to * self.stride]
+    }
+}
+
+/// Rows that stay in place between frames, like sticky headers and footers.
+fn static_rows(previous: &Frame, next: &Frame) -> (usize, usize) {
+    let header = previous
+        .hashes
+        .iter()
+        .zip(&next.hashes)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let footer = previous.hashes[header..]
+        .iter()
+        .rev()
+        .zip(next.hashes[header..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (header, footer)
+}
+
+/// The longest run of rows ending `previous` that also starts `next`.
+fn overlap(previous: &[u64], next: &[u64]) -> Option<usize> {
+    (MIN_OVERLAP..next.len().min(previous.len()))
+        .rev()
+        .find(|&rows| previous[previous.len() - rows..] == next[..rows])
+}
+
+/// Joins the scrolling part of all frames, framed by the first header and the last footer.
+struct Stitcher {
+    first: Frame,
+    previous: Option<Frame>,
+    /// Sticky rows, found from the first two frames that differ.
+    bands: Option<(usize, usize)>,
+    body: Vec<u8>,
+}
+
+impl Stitcher {
+    fn new(first: Frame) -> Self {
+        Stitcher {
+            first,
+            previous: None,
+            bands: None,
+            body: Vec::new(),
+        }
+    }
+
+    /// Returns whether the frame added anything.
+    fn push(&mut self, next: Frame) -> Result<bool> {
+        let previous = self.previous.as_ref().unwrap_or(&self.first);
+        if next.width != previous.width || next.height() != previous.height() {
+            return Err(anyhow!("The captured region changed size while scrolling"));
+        }
+        if previous.hashes == next.hashes {
+            return Ok(false);
+        }
+        let height = next.height();
+        let (header, footer) = *self
+            .bands
+            .get_or_insert_with(|| static_rows(previous, &next));
+        let scrolled = height - footer;
+        if header >= scrolled || previous.hashes[header..scrolled] == next.hashes[header..scrolled]
+        {
+            return Ok(false);
+        }
+        if self.body.is_empty() {
+            self.body = previous.rows(header, scrolled).to_vec();
+        }
+        let rows = overlap(
+            &previous.hashes[header..scrolled],
+            &next.hashes[header..scrolled],
+        )
+        // scrolled by more than a whole region, nothing lines up
+        .unwrap_or(0);
+        self.body
+            .extend_from_slice(next.rows(header + rows, scrolled));
+        self.previous = Some(next);
+        Ok(true)
+    }
+
+    fn finish(self) -> Result<Texture> {
+        let (width, stride) = (self.first.width, self.first.stride);
+        let data = match (self.bands, &self.previous) {
+            (Some((header, footer)), Some(last)) => {
+                let height = last.height();
+                [
+                    self.first.rows(0, header),
+                    &self.body,
+                    last.rows(height - footer, height),
+                ]
+                .concat()
+            }
+            _ => self.first.data,
+        };
+        let height = (data.len() / stride) as i32;
+        let mut surface = ImageSurface::create(Format::ARgb32, width, height)
+            .context("creating stitched image")?;
+        let surface_stride = surface.stride() as usize;
+        {
+            let mut target = surface.data().context("accessing stitched image")?;
+            for (row, source) in data.chunks(stride).enumerate() {
+                target[row * surface_stride..row * surface_stride + width as usize * 4]
+                    .copy_from_slice(&source[..width as usize * 4]);
+            }
+        }
+        texture_from_surface(surface)
+    }
+}
+
+async fn capture(region: &str) -> Result<Frame> {
+    let png = run(
+        &[
+            OsStr::new("grim"),
+            OsStr::new("-g"),
+            OsStr::new(region),
+            OsStr::new("-"),
+        ],
+        None,
+    )
+    .await?;
+    Frame::new(&Texture::from_bytes(&png).context("decoding captured frame")?)
+}
+
+/// Captures the region while the user scrolls it, until it stops moving or is activated again.
+async fn scroll_capture(running: &Rc<Cell<bool>>, wait_seconds: u32) -> Result<Bytes> {
+    timeout_future(Duration::from_secs(wait_seconds.into())).await;
+
+    let region = select_region().await?;
+    let mut stitcher = Stitcher::new(capture(&region).await?);
+    let mut still = 0;
+    for _ in 1..MAX_FRAMES {
+        timeout_future(INTERVAL).await;
+        if !running.get() {
+            break;
+        }
+        if stitcher.push(capture(&region).await?)? {
+            still = 0;
+        } else {
+            still += 1;
+            if still >= STILL_FRAMES {
+                break;
+            }
+        }
+    }
+    Ok(stitcher.finish()?.save_to_png_bytes())
+}
+
+/// Starts a scrolling capture, or finishes the running one early.
+pub(crate) async fn handler_inner(
+    running: &Rc<Cell<bool>>,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    delay_button: &SpinButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) {
+    if running.replace(true) {
+        running.set(false);
+        return;
+    }
+    window.set_visible(false);
+    let result = scroll_capture(running, delay_button.value() as u32)
+        .await
+        .and_then(|bytes| {
+            let document = Document::new(bytes)?;
+            let texture = document.texture().clone();
+            image.replace(Some(document));
+            Ok(texture)
+        });
+    running.set(false);
+    match result {
+        Ok(texture) => {
+            show_texture(image_view, &texture);
+            image_revealer.set_reveal_child(true);
+            error_revealer.set_reveal_child(false);
+        }
+        Err(e) => {
+            error_label.set_text(&format!("{:?}", e));
+            error_revealer.set_reveal_child(true);
+        }
+    }
+    window.set_visible(true);
+}
+
+pub(crate) fn get_handler(
+    last_shot: &Rc<Cell<ShotType>>,
+    main_context: &MainContext,
+    running: &Rc<Cell<bool>>,
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    image_revealer: &Revealer,
+    delay_button: &SpinButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+    window: &ApplicationWindow,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong last_shot,
+        @strong main_context,
+        @strong running,
+        @strong image,
+        @strong image_view,
+        @strong image_revealer,
+        @strong delay_button,
+        @strong error_revealer,
+        @strong error_label,
+        @weak window
+            => move |_| {
+                last_shot.set(ShotType::Scrolling);
+                main_context.spawn_local(clone!(
+                    @strong running,
+                    @strong image,
+                    @strong image_view,
+                    @strong image_revealer,
+                    @strong delay_button,
+                    @strong error_revealer,
+                    @strong error_label,
+                    @weak window
+                        => async move {
+                            handler_inner(
+                                &running,
+                                &image,
+                                &image_view,
+                                &image_revealer,
+                                &delay_button,
+                                &error_revealer,
+                                &error_label,
+                                &window,
+                            )
+                            .await
+                        }
+                ));
+            }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame one pixel wide whose rows are filled with the given values.
+    fn frame(rows: impl IntoIterator<Item = u32>) -> Frame {
+        let data: Vec<u8> = rows.into_iter().flat_map(u32::to_le_bytes).collect();
+        Frame::from_data(1, 4, data)
+    }
+
+    /// A page with a two row header and footer, showing content rows from `top`.
+    fn page(top: u32) -> Frame {
+        frame(
+            [1000, 1001]
+                .into_iter()
+                .chain(top..top + 30)
+                .chain([2000, 2001]),
+        )
+    }
+
+    #[test]
+    fn overlap_finds_scrolled_rows() {
+        let previous: Vec<u64> = (0..40).collect();
+        let next: Vec<u64> = (10..50).collect();
+        assert_eq!(overlap(&previous, &next), Some(30));
+    }
+
+    #[test]
+    fn overlap_ignores_short_matches() {
+        let previous: Vec<u64> = (0..40).collect();
+        let next: Vec<u64> = (30..70).collect();
+        assert_eq!(overlap(&previous, &next), None);
+    }
+
+    #[test]
+    fn static_rows_finds_header_and_footer() {
+        assert_eq!(static_rows(&page(0), &page(10)), (2, 2));
+    }
+
+    #[test]
+    fn stitcher_appends_only_new_rows() {
+        let mut stitcher = Stitcher::new(page(0));
+        assert!(stitcher.push(page(10)).unwrap());
+        assert!(stitcher.push(page(20)).unwrap());
+        assert_eq!(stitcher.bands, Some((2, 2)));
+        assert_eq!(stitcher.body, frame(0..50).data);
+    }
+
+    #[test]
+    fn stitcher_skips_unchanged_frames() {
+        let mut stitcher = Stitcher::new(page(0));
+        assert!(!stitcher.push(page(0)).unwrap());
+        assert!(stitcher.push(page(10)).unwrap());
+        assert!(!stitcher.push(page(10)).unwrap());
+        assert_eq!(stitcher.body, frame(0..40).data);
+    }
+
+    #[test]
+    fn stitcher_rejects_resized_frames() {
+        let mut stitcher = Stitcher::new(page(0));
+        assert!(stitcher.push(frame(0..10)).is_err());
+    }
+}
@@ -1,29 +1,30 @@
 use std::{
     cell::{Ref, RefCell},
-    ops::Deref,
     rc::Rc,
 };
 
 use anyhow::{anyhow, Context, Result};
 use gtk4::{
     gdk::{Clipboard, ContentProvider},
-    glib::{clone, Bytes},
+    glib::clone,
     Button, Label, Revealer,
 };
 
-fn set_cliboard(image: &Rc<RefCell<Option<Bytes>>>, clipboard: &Clipboard) -> Result<()> {
+use crate::editor::Document;
+
+fn set_cliboard(image: &Rc<RefCell<Option<Document>>>, clipboard: &Clipboard) -> Result<()> {
     clipboard
         .set_content(Some(&ContentProvider::for_bytes(
             "image/png",
-            Ref::filter_map(image.borrow(), Option::as_ref)
+            &Ref::filter_map(image.borrow(), Option::as_ref)
                 .map_err(|_| anyhow!("No screenshot available to save"))?
-                .deref(),
+                .bytes(),
         )))
         .context("Saving Image to Clipboard")
 }
 
 fn handler(
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     clipboard: &Clipboard,
     error_revealer: &Revealer,
     error_label: &Label,
@@ -35,7 +36,7 @@ fn handler(
 }
 
 pub fn get_handler(
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     clipboard: &Clipboard,
     error_revealer: &Revealer,
     error_label: &Label,
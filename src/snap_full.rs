@@ -2,57 +2,35 @@ use std::{
     cell::{Cell, RefCell},
     ffi::OsStr,
     rc::Rc,
-    str::from_utf8,
 };
 
 use gtk4::{
-    gdk::Texture,
-    gio::{Subprocess, SubprocessFlags},
     glib::{self, clone, timeout_future_seconds, Bytes, MainContext},
-    prelude::TextureExt,
     traits::{CheckButtonExt, WidgetExt},
     ApplicationWindow, Button, CheckButton, Label, Picture, Revealer, SpinButton,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::Result;
 
-use crate::ShotType;
+use crate::{
+    command::run,
+    editor::{show_texture, Document},
+    ShotType,
+};
 
 async fn snap_full(cursor: bool, wait_seconds: u32) -> Result<Bytes> {
     timeout_future_seconds(wait_seconds).await;
 
-    let with_c = [OsStr::new("grim"), OsStr::new("-c"), OsStr::new("-")];
-    let without_c = [OsStr::new("grim"), OsStr::new("-")];
-    let grim = Subprocess::newv(
-        if cursor { &with_c } else { &without_c },
-        SubprocessFlags::STDOUT_PIPE | SubprocessFlags::STDERR_PIPE,
-    )
-    .context("spawning grim")?;
-    let (out, err) = grim
-        .communicate_future(None)
-        .await
-        .context("receiving output from grim")?;
-    if grim.is_successful() {
-        Ok(out.expect("stdout output"))
-    } else {
-        let err = err.expect("stderr output");
-        if err.is_empty() {
-            Err(anyhow!(
-                "grim failed with exit status {} but no error output was provided",
-                grim.exit_status()
-            ))
-        } else {
-            Err(anyhow!(
-                "grim failed with exit status {}:\n{}",
-                grim.exit_status(),
-                from_utf8(&err).context("decoding grom stderr output")?
-            ))
-        }
+    let mut argv = vec![OsStr::new("grim")];
+    if cursor {
+        argv.push(OsStr::new("-c"));
     }
+    argv.push(OsStr::new("-"));
+    run(&argv, None).await
 }
 
 pub(crate) async fn handler_inner(
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     image_view: &Picture,
     image_revealer: &Revealer,
     delay_button: &SpinButton,
@@ -64,15 +42,14 @@ pub(crate) async fn handler_inner(
     let image = snap_full(cursor_check.is_active(), delay_button.value() as u32)
         .await
         .and_then(move |bytes| {
-            let texture = Texture::from_bytes(&bytes).context("loading screenshot image")?;
-            image.replace(Some(bytes));
+            let document = Document::new(bytes)?;
+            let texture = document.texture().clone();
+            image.replace(Some(document));
             Ok(texture)
         });
     match image {
         Ok(texture) => {
-            image_view.set_paintable(Some(&texture));
-            image_view.set_width_request(texture.width());
-            image_view.set_height_request(texture.height());
+            show_texture(image_view, &texture);
             image_revealer.set_reveal_child(true);
             error_revealer.set_reveal_child(false);
             window.set_visible(true);
@@ -88,7 +65,7 @@ pub(crate) async fn handler_inner(
 fn handler(
     last_shot: &Rc<Cell<ShotType>>,
     main_context: &MainContext,
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     image_view: &Picture,
     image_revealer: &Revealer,
     delay_button: &SpinButton,
@@ -116,7 +93,7 @@ fn handler(
 pub(crate) fn get_handler(
     last_shot: &Rc<Cell<ShotType>>,
     main_context: &MainContext,
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     image_view: &Picture,
     image_revealer: &Revealer,
     delay_button: &SpinButton,
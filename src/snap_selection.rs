@@ -2,128 +2,70 @@ use std::{
     cell::{Cell, RefCell},
     ffi::OsStr,
     rc::Rc,
-    str::from_utf8,
 };
 
 use gtk4::{
-    gdk::Texture,
-    gio::{Subprocess, SubprocessFlags},
     glib::{self, clone, timeout_future_seconds, Bytes, MainContext},
-    prelude::TextureExt,
     traits::{CheckButtonExt, WidgetExt},
     ApplicationWindow, Button, CheckButton, Label, Picture, Revealer, SpinButton,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::Result;
 
-use crate::{KillSubprocessGuard, ShotType};
+use crate::{
+    command::{run, select_region},
+    editor::{show_texture, Document},
+    ShotType,
+};
 
-async fn snap_selection(cursor: bool, wait_seconds: u32) -> Result<Bytes> {
+/// Lets the user select a region and captures it, on the frozen screen when `freeze` is set.
+pub(crate) async fn snap_selection(cursor: bool, freeze: bool, wait_seconds: u32) -> Result<Bytes> {
     timeout_future_seconds(wait_seconds).await;
 
-    let _hyprpicker = KillSubprocessGuard::new(
-        Subprocess::newv(
-            &[OsStr::new("wayfreeze"), OsStr::new("--hide-cursor")],
-            SubprocessFlags::NONE,
-        )
-        .context("spawning wayfreeze")?,
-    );
-
-    let slurp = Subprocess::newv(
-        &[OsStr::new("slurp")],
-        SubprocessFlags::STDOUT_PIPE | SubprocessFlags::STDERR_PIPE,
-    )
-    .context("spawning slurp")?;
-    let (out, err) = slurp
-        .communicate_utf8_future(None)
-        .await
-        .context("receiving output from grim")?;
-    let selection = if slurp.is_successful() {
-        Ok(out.expect("stdout output"))
+    let frozen = if freeze {
+        Some(crate::freeze::freeze(cursor).await?)
     } else {
-        let err = err.expect("stderr output");
-        if err.is_empty() {
-            Err(anyhow!(
-                "slurp failed with exit status {} but no error output was provided",
-                slurp.exit_status()
-            ))
-        } else {
-            Err(anyhow!(
-                "grim failed with exit status {}:\n{}",
-                slurp.exit_status(),
-                err
-            ))
-        }
-    }?;
-    let selection = selection.trim_end();
-    let grim_with_c = [
-        OsStr::new("grim"),
-        OsStr::new("-g"),
-        OsStr::new(&selection),
-        OsStr::new("-c"),
-        OsStr::new("-"),
-    ];
-    let grim_without_c = [
-        OsStr::new("grim"),
-        OsStr::new("-g"),
-        OsStr::new(&selection),
-        OsStr::new("-"),
-    ];
-    let grim = Subprocess::newv(
-        if cursor {
-            &grim_with_c
-        } else {
-            &grim_without_c
-        },
-        SubprocessFlags::STDOUT_PIPE | SubprocessFlags::STDERR_PIPE,
-    )
-    .context("spawning grim")?;
-    let (out, err) = grim
-        .communicate_future(None)
-        .await
-        .context("receiving output from grim")?;
-
-    if grim.is_successful() {
-        Ok(out.expect("stdout output"))
-    } else {
-        let err = err.expect("stderr output");
-        if err.is_empty() {
-            Err(anyhow!(
-                "grim failed with exit status {} but no error output was provided",
-                grim.exit_status()
-            ))
-        } else {
-            Err(anyhow!(
-                "grim failed with exit status {}:\n{}",
-                grim.exit_status(),
-                from_utf8(&err).context("decoding grom stderr output")?
-            ))
-        }
+        None
+    };
+    let selection = select_region().await?;
+    let mut argv = vec![OsStr::new("grim"), OsStr::new("-g"), OsStr::new(&selection)];
+    // the frozen image already contains the cursor if it was asked for
+    if cursor && frozen.is_none() {
+        argv.push(OsStr::new("-c"));
     }
+    argv.push(OsStr::new("-"));
+    // captured while still frozen, so the image matches what the selection was made on
+    let png = run(&argv, None).await;
+    drop(frozen);
+    png
 }
 
 pub(crate) async fn handler_inner(
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     image_view: &Picture,
     image_revealer: &Revealer,
     delay_button: &SpinButton,
     cursor_check: &CheckButton,
+    freeze_check: &CheckButton,
     error_revealer: &Revealer,
     error_label: &Label,
     window: &ApplicationWindow,
 ) {
-    let image = snap_selection(cursor_check.is_active(), delay_button.value() as u32)
-        .await
-        .and_then(move |bytes| {
-            let texture = Texture::from_bytes(&bytes).context("loading screenshot image")?;
-            image.replace(Some(bytes));
-            Ok(texture)
-        });
+    let image = snap_selection(
+        cursor_check.is_active(),
+        freeze_check.is_active(),
+        delay_button.value() as u32,
+    )
+    .await
+    .and_then(move |bytes| {
+        let document = Document::new(bytes)?;
+        let texture = document.texture().clone();
+        image.replace(Some(document));
+        Ok(texture)
+    });
     match image {
         Ok(texture) => {
-            image_view.set_paintable(Some(&texture));
-            image_view.set_width_request(texture.width());
-            image_view.set_height_request(texture.height());
+            show_texture(image_view, &texture);
             image_revealer.set_reveal_child(true);
             error_revealer.set_reveal_child(false);
             window.set_visible(true);
@@ -139,11 +81,12 @@ pub(crate) async fn handler_inner(
 fn handler(
     last_shot: &Rc<Cell<ShotType>>,
     main_context: &MainContext,
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     image_view: &Picture,
     image_revealer: &Revealer,
     delay_button: &SpinButton,
     cursor_check: &CheckButton,
+    freeze_check: &CheckButton,
     error_revealer: &Revealer,
     error_label: &Label,
     window: &ApplicationWindow,
@@ -157,22 +100,24 @@ fn handler(
         @strong image_revealer,
         @strong delay_button,
         @strong cursor_check,
+        @strong freeze_check,
         @strong error_revealer,
         @strong error_label,
         @weak window
             => async move{
-                handler_inner(&image, &image_view, &image_revealer, &delay_button, &cursor_check, &error_revealer, &error_label, &window).await
+                handler_inner(&image, &image_view, &image_revealer, &delay_button, &cursor_check, &freeze_check, &error_revealer, &error_label, &window).await
 
     }));
 }
 pub(crate) fn get_handler(
     last_shot: &Rc<Cell<ShotType>>,
     main_context: &MainContext,
-    image: &Rc<RefCell<Option<Bytes>>>,
+    image: &Rc<RefCell<Option<Document>>>,
     image_view: &Picture,
     image_revealer: &Revealer,
     delay_button: &SpinButton,
     cursor_check: &CheckButton,
+    freeze_check: &CheckButton,
     error_revealer: &Revealer,
     error_label: &Label,
     window: &ApplicationWindow,
@@ -185,10 +130,11 @@ pub(crate) fn get_handler(
             @strong image_revealer,
             @strong delay_button,
             @strong cursor_check,
+            @strong freeze_check,
             @strong error_revealer,
             @strong error_label,
             @weak window
                 => move |_|{
-    handler(&last_shot,&main_context, &image, &image_view, &image_revealer,& delay_button, &cursor_check,& error_revealer,& error_label,& window)
+    handler(&last_shot,&main_context, &image, &image_view, &image_revealer,& delay_button, &cursor_check, &freeze_check,& error_revealer,& error_label,& window)
                         })
 }
@@ -0,0 +1,169 @@
+use std::{
+    cell::RefCell,
+    ffi::{OsStr, OsString},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+
+/// The helpers whose executable and arguments can be configured.
+pub(crate) const CONFIGURABLE: [&str; 3] = ["grim", "slurp", "wayfreeze"];
+
+/// Where to find a helper and what to always pass it, e.g. `-l 3` for grim's compression.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Tool {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) args: Vec<String>,
+}
+
+impl Tool {
+    /// `SHOTS_GRIM` and `SHOTS_GRIM_ARGS` override grim's entries, arguments are split like a
+    /// shell would.
+    fn with_env(self, name: &str) -> Result<Self> {
+        let variable = format!("SHOTS_{}", name.to_uppercase());
+        let path = std::env::var_os(&variable)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or(self.path);
+        let args = match std::env::var(format!("{variable}_ARGS")) {
+            Ok(args) if !args.trim().is_empty() => glib::shell_parse_argv(&args)
+                .with_context(|| format!("parsing {variable}_ARGS"))?
+                .into_iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            _ => self.args,
+        };
+        Ok(Tool { path, args })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Tools {
+    pub(crate) grim: Tool,
+    pub(crate) slurp: Tool,
+    pub(crate) wayfreeze: Tool,
+}
+
+impl Tools {
+    pub(crate) fn with_env(self) -> Result<Self> {
+        Ok(Tools {
+            grim: self.grim.with_env("grim")?,
+            slurp: self.slurp.with_env("slurp")?,
+            wayfreeze: self.wayfreeze.with_env("wayfreeze")?,
+        })
+    }
+
+    pub(crate) fn get(&self, name: &OsStr) -> Option<&Tool> {
+        match name.to_str()? {
+            "grim" => Some(&self.grim),
+            "slurp" => Some(&self.slurp),
+            "wayfreeze" => Some(&self.wayfreeze),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, name: &str) -> Option<&mut Tool> {
+        match name {
+            "grim" => Some(&mut self.grim),
+            "slurp" => Some(&mut self.slurp),
+            "wayfreeze" => Some(&mut self.wayfreeze),
+            _ => None,
+        }
+    }
+}
+
+thread_local! {
+    // read where the command lines are built, deep inside the capture modes
+    static TOOLS: RefCell<Tools> = RefCell::new(Tools::default());
+}
+
+pub(crate) fn set(tools: Tools) {
+    TOOLS.with(|current| current.replace(tools));
+}
+
+/// The executable to run for a helper.
+pub(crate) fn program(name: &str) -> OsString {
+    TOOLS.with(|tools| {
+        tools
+            .borrow()
+            .get(OsStr::new(name))
+            .and_then(|tool| tool.path.clone())
+            .map_or_else(|| OsString::from(name), PathBuf::into_os_string)
+    })
+}
+
+/// Replaces the program with its configured path and adds its extra arguments right after it.
+pub(crate) fn command_line(argv: &[&OsStr]) -> Vec<OsString> {
+    TOOLS.with(|tools| {
+        let tools = tools.borrow();
+        let Some(tool) = tools.get(argv[0]) else {
+            return argv.iter().map(OsString::from).collect();
+        };
+        let program = tool
+            .path
+            .clone()
+            .map_or_else(|| argv[0].to_os_string(), PathBuf::into_os_string);
+        std::iter::once(program)
+            .chain(tool.args.iter().map(OsString::from))
+            .chain(argv[1..].iter().map(OsString::from))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(argv: &[OsString]) -> Vec<&str> {
+        argv.iter().map(|arg| arg.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn command_line_without_configuration() {
+        set(Tools::default());
+        let argv = command_line(&[OsStr::new("grim"), OsStr::new("-")]);
+        assert_eq!(strings(&argv), ["grim", "-"]);
+    }
+
+    #[test]
+    fn command_line_with_path_and_arguments() {
+        set(Tools {
+            grim: Tool {
+                path: Some(PathBuf::from("/opt/grim/bin/grim")),
+                args: vec!["-l".to_string(), "3".to_string()],
+            },
+            ..Tools::default()
+        });
+        let argv = command_line(&[
+            OsStr::new("grim"),
+            OsStr::new("-g"),
+            OsStr::new("0,0 10x10"),
+            OsStr::new("-"),
+        ]);
+        assert_eq!(
+            strings(&argv),
+            ["/opt/grim/bin/grim", "-l", "3", "-g", "0,0 10x10", "-"]
+        );
+        assert_eq!(program("grim"), "/opt/grim/bin/grim");
+    }
+
+    #[test]
+    fn command_line_keeps_other_programs() {
+        set(Tools {
+            slurp: Tool {
+                path: None,
+                args: vec!["-d".to_string()],
+            },
+            ..Tools::default()
+        });
+        let argv = command_line(&[OsStr::new("wf-recorder"), OsStr::new("-y")]);
+        assert_eq!(strings(&argv), ["wf-recorder", "-y"]);
+        let argv = command_line(&[OsStr::new("slurp"), OsStr::new("-o")]);
+        assert_eq!(strings(&argv), ["slurp", "-d", "-o"]);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::{anyhow, Context, Result};
+use gtk4::{glib::clone, Button, Label, Picture, Revealer, SpinButton};
+
+use crate::editor::{show_texture, surface_from_texture, Document, Operation};
+
+struct Pixels<'a> {
+    data: &'a [u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Pixels<'_> {
+    fn pixel(&self, x: usize, y: usize) -> &[u8] {
+        let offset = y * self.stride + x * 4;
+        &self.data[offset..offset + 4]
+    }
+}
+
+/// Finds the bounds of the content inside a border that is either (nearly) transparent
+/// or matches the colour of the top left pixel within `tolerance`.
+fn content_bounds(pixels: &Pixels, tolerance: u8) -> Option<(usize, usize, usize, usize)> {
+    let background: [u8; 4] = pixels.pixel(0, 0).try_into().ok()?;
+    let is_border = |x: usize, y: usize| {
+        let pixel = pixels.pixel(x, y);
+        // alpha is the fourth byte in little endian and the first byte in big endian order
+        let alpha = if cfg!(target_endian = "little") {
+            pixel[3]
+        } else {
+            pixel[0]
+        };
+        alpha <= tolerance
+            || pixel
+                .iter()
+                .zip(background)
+                .all(|(channel, background)| channel.abs_diff(background) <= tolerance)
+    };
+    let row_is_border = |y: usize| (0..pixels.width).all(|x| is_border(x, y));
+    let column_is_border =
+        |x: usize, top: usize, bottom: usize| (top..bottom).all(|y| is_border(x, y));
+
+    let top = (0..pixels.height).find(|&y| !row_is_border(y))?;
+    let bottom = (top..pixels.height).rfind(|&y| !row_is_border(y))? + 1;
+    let left = (0..pixels.width).find(|&x| !column_is_border(x, top, bottom))?;
+    let right = (left..pixels.width).rfind(|&x| !column_is_border(x, top, bottom))? + 1;
+    Some((left, top, right - left, bottom - top))
+}
+
+fn trim(image: &Rc<RefCell<Option<Document>>>, image_view: &Picture, tolerance: u8) -> Result<()> {
+    let mut document = image.borrow_mut();
+    let document = document
+        .as_mut()
+        .ok_or_else(|| anyhow!("No screenshot available to trim"))?;
+    let mut surface = surface_from_texture(document.texture())?;
+    let stride = surface.stride() as usize;
+    let (width, height) = (surface.width() as usize, surface.height() as usize);
+    let data = surface.data().context("accessing image surface")?;
+    let (x, y, trimmed_width, trimmed_height) = content_bounds(
+        &Pixels {
+            data: &data,
+            stride,
+            width,
+            height,
+        },
+        tolerance,
+    )
+    .ok_or_else(|| anyhow!("Screenshot contains no content to trim to"))?;
+    if (trimmed_width, trimmed_height) != (width, height) {
+        document
+            .push(Operation::Crop {
+                x: x as i32,
+                y: y as i32,
+                width: trimmed_width as i32,
+                height: trimmed_height as i32,
+            })
+            .context("applying auto crop")?;
+        show_texture(image_view, document.texture());
+    }
+    Ok(())
+}
+
+pub(crate) fn get_handler(
+    image: &Rc<RefCell<Option<Document>>>,
+    image_view: &Picture,
+    tolerance_button: &SpinButton,
+    error_revealer: &Revealer,
+    error_label: &Label,
+) -> impl Fn(&Button) {
+    clone!(
+        @strong image,
+        @strong image_view,
+        @strong tolerance_button,
+        @strong error_revealer,
+        @strong error_label
+            => move |_| {
+                if let Err(e) = trim(&image, &image_view, tolerance_button.value() as u8) {
+                    error_label.set_text(&format!("{e:?}"));
+                    error_revealer.set_reveal_child(true);
+                }
+            }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: u32 = 0xffff_ffff;
+    const RED: u32 = 0xffff_0000;
+    const TRANSPARENT: u32 = 0;
+
+    /// Cairo ARGB32 data with two bytes of padding after each row.
+    fn data(width: usize, height: usize, pixel: impl Fn(usize, usize) -> u32) -> Vec<u8> {
+        (0..height)
+            .flat_map(|y| {
+                (0..width)
+                    .flat_map(|x| pixel(x, y).to_ne_bytes())
+                    .chain([0; 2])
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn bounds(
+        width: usize,
+        height: usize,
+        pixel: impl Fn(usize, usize) -> u32,
+    ) -> Option<(usize, usize, usize, usize)> {
+        let data = data(width, height, pixel);
+        content_bounds(
+            &Pixels {
+                data: &data,
+                stride: width * 4 + 2,
+                width,
+                height,
+            },
+            8,
+        )
+    }
+
+    #[test]
+    fn trims_uniform_border() {
+        let pixel = |x, y| {
+            if (2..5).contains(&x) && (1..3).contains(&y) {
+                RED
+            } else if x == 0 && y == 0 {
+                WHITE
+            } else {
+                // within the tolerance of the top left pixel
+                0xfffa_fbfc
+            }
+        };
+        assert_eq!(bounds(8, 6, pixel), Some((2, 1, 3, 2)));
+    }
+
+    #[test]
+    fn trims_transparent_border() {
+        let pixel = |x, y| {
+            if (1..7).contains(&x) && (3..6).contains(&y) {
+                WHITE
+            } else {
+                // nearly transparent, whatever its colour
+                0x0512_3456 * (x as u32 % 2)
+            }
+        };
+        assert_eq!(bounds(8, 8, pixel), Some((1, 3, 6, 3)));
+    }
+
+    #[test]
+    fn uniform_image_has_no_content() {
+        assert_eq!(bounds(5, 4, |_, _| WHITE), None);
+        assert_eq!(bounds(5, 4, |_, _| TRANSPARENT), None);
+    }
+
+    #[test]
+    fn keeps_image_without_border() {
+        let pixel = |x, y| if (x + y) % 2 == 0 { WHITE } else { RED };
+        assert_eq!(bounds(4, 3, pixel), Some((0, 0, 4, 3)));
+    }
+}
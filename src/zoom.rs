@@ -0,0 +1,251 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk4::{
+    cairo::{Filter, ImageSurface},
+    gdk::{ModifierType, PaintableFlags, Texture, BUTTON_MIDDLE, BUTTON_PRIMARY},
+    glib::{self, clone, Propagation},
+    prelude::{
+        AdjustmentExt, DrawingAreaExt, DrawingAreaExtManual, EventControllerExt, GestureDragExt,
+        GestureExt, GestureSingleExt, PaintableExt, WidgetExt,
+    },
+    DrawingArea, DropDown, EventControllerScroll, EventControllerScrollFlags, EventSequenceState,
+    GestureDrag, GestureZoom, Label, Picture, PropagationPhase, ScrolledWindow,
+};
+
+use crate::{
+    annotate::Tool,
+    editor::{surface_from_texture, Document},
+};
+
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 32.0;
+const ZOOM_STEP: f64 = 1.25;
+const SCROLL_STEP: f64 = 1.1;
+/// From this scale on pixels are drawn as sharp squares instead of being interpolated.
+const NEAREST_SCALE: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Zoom {
+    Fit,
+    Scale(f64),
+}
+
+/// Sizes the preview according to the selected zoom and keeps the tool layers informed about
+/// the scale between image and widget coordinates.
+///
+/// `frame` is the main child of the preview overlay and determines its size, the picture and
+/// all layers are overlays stretched across it.
+#[derive(Clone)]
+pub(crate) struct ZoomView {
+    image_scroll: ScrolledWindow,
+    image_view: Picture,
+    frame: DrawingArea,
+    pixels: DrawingArea,
+    layers: Vec<DrawingArea>,
+    zoom_label: Label,
+    zoom: Rc<Cell<Zoom>>,
+    scale: Rc<Cell<f64>>,
+}
+
+impl ZoomView {
+    pub(crate) fn new(
+        image_scroll: &ScrolledWindow,
+        image_view: &Picture,
+        frame: &DrawingArea,
+        pixels: &DrawingArea,
+        layers: &[&DrawingArea],
+        zoom_label: &Label,
+        scale: &Rc<Cell<f64>>,
+    ) -> Self {
+        ZoomView {
+            image_scroll: image_scroll.clone(),
+            image_view: image_view.clone(),
+            frame: frame.clone(),
+            pixels: pixels.clone(),
+            layers: layers.iter().map(|&layer| layer.clone()).collect(),
+            zoom_label: zoom_label.clone(),
+            zoom: Rc::new(Cell::new(Zoom::Fit)),
+            scale: scale.clone(),
+        }
+    }
+
+    pub(crate) fn set(&self, zoom: Zoom) {
+        self.zoom.set(match zoom {
+            Zoom::Fit => Zoom::Fit,
+            Zoom::Scale(scale) => Zoom::Scale(scale.clamp(MIN_SCALE, MAX_SCALE)),
+        });
+        self.update();
+    }
+
+    pub(crate) fn zoom_by(&self, factor: f64) {
+        self.set(Zoom::Scale(self.scale.get() * factor));
+    }
+
+    pub(crate) fn zoom_in(&self) {
+        self.zoom_by(ZOOM_STEP);
+    }
+
+    pub(crate) fn zoom_out(&self) {
+        self.zoom_by(1.0 / ZOOM_STEP);
+    }
+
+    fn update(&self) {
+        let Some(paintable) = self.image_view.paintable() else {
+            return;
+        };
+        let width = paintable.intrinsic_width() as f64;
+        let height = paintable.intrinsic_height() as f64;
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        let scale = match self.zoom.get() {
+            Zoom::Fit => {
+                let page_width = self.image_scroll.hadjustment().page_size();
+                let page_height = self.image_scroll.vadjustment().page_size();
+                if page_width > 0.0 && page_height > 0.0 {
+                    (page_width / width).min(page_height / height).min(1.0)
+                } else {
+                    1.0
+                }
+            }
+            Zoom::Scale(scale) => scale,
+        };
+        self.scale.set(scale);
+        self.frame.set_content_width((width * scale).round() as i32);
+        self.frame
+            .set_content_height((height * scale).round() as i32);
+        self.zoom_label.set_text(&format!("{:.0}%", scale * 100.0));
+        self.pixels.queue_draw();
+        for layer in &self.layers {
+            layer.queue_draw();
+        }
+    }
+}
+
+pub(crate) fn connect_zoom(
+    zoom_view: &ZoomView,
+    image: &Rc<RefCell<Option<Document>>>,
+    tool_drop: &DropDown,
+) {
+    let image_scroll = zoom_view.image_scroll.clone();
+    let scale = zoom_view.scale.clone();
+
+    zoom_view
+        .image_view
+        .connect_paintable_notify(clone!(@strong zoom_view => move |image_view| {
+            // videos only know their size once they are loaded
+            if let Some(paintable) = image_view
+                .paintable()
+                .filter(|paintable| !paintable.flags().contains(PaintableFlags::SIZE))
+            {
+                paintable.connect_invalidate_size(
+                    clone!(@strong zoom_view => move |_| zoom_view.update()),
+                );
+            }
+            zoom_view.update()
+        }));
+    image_scroll.hadjustment().connect_page_size_notify(
+        clone!(@strong zoom_view => move |_| if zoom_view.zoom.get() == Zoom::Fit {
+            zoom_view.update()
+        }),
+    );
+    image_scroll.vadjustment().connect_page_size_notify(
+        clone!(@strong zoom_view => move |_| if zoom_view.zoom.get() == Zoom::Fit {
+            zoom_view.update()
+        }),
+    );
+
+    // the picture interpolates when scaled, so at high zoom levels the pixels are drawn on top of it
+    let surface_cache: Rc<RefCell<Option<(Texture, ImageSurface)>>> = Rc::new(RefCell::new(None));
+    zoom_view.pixels.set_can_target(false);
+    zoom_view.pixels.set_draw_func(clone!(
+        @strong image,
+        @strong surface_cache,
+        @strong scale
+            => move |_, cr, _, _| {
+                let scale = scale.get();
+                if scale < NEAREST_SCALE {
+                    return;
+                }
+                let image = image.borrow();
+                let Some(document) = image.as_ref() else {
+                    return;
+                };
+                let mut surface_cache = surface_cache.borrow_mut();
+                if surface_cache.as_ref().map(|(texture, _)| texture) != Some(document.texture()) {
+                    *surface_cache = surface_from_texture(document.texture())
+                        .ok()
+                        .map(|surface| (document.texture().clone(), surface));
+                }
+                if let Some((_, surface)) = surface_cache.as_ref() {
+                    cr.scale(scale, scale);
+                    if cr.set_source_surface(surface, 0.0, 0.0).is_ok() {
+                        cr.source().set_filter(Filter::Nearest);
+                        let _ = cr.paint();
+                    }
+                }
+            }
+    ));
+
+    let scroll = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+    scroll.set_propagation_phase(PropagationPhase::Capture);
+    scroll.connect_scroll(clone!(@strong zoom_view => move |scroll, _, dy| {
+        if scroll.current_event_state().contains(ModifierType::CONTROL_MASK) {
+            zoom_view.zoom_by(SCROLL_STEP.powf(-dy));
+            Propagation::Stop
+        } else {
+            Propagation::Proceed
+        }
+    }));
+    image_scroll.add_controller(scroll);
+
+    let pinch = GestureZoom::new();
+    let pinch_start = Rc::new(Cell::new(1.0));
+    pinch.connect_begin(
+        clone!(@strong zoom_view, @strong pinch_start => move |_, _| {
+            pinch_start.set(zoom_view.scale.get());
+        }),
+    );
+    pinch.connect_scale_changed(
+        clone!(@strong zoom_view, @strong pinch_start => move |_, scale| {
+            zoom_view.set(Zoom::Scale(pinch_start.get() * scale));
+        }),
+    );
+    image_scroll.add_controller(pinch);
+
+    let pan = GestureDrag::builder()
+        .button(0)
+        .propagation_phase(PropagationPhase::Capture)
+        .build();
+    let pan_start = Rc::new(Cell::new((0.0, 0.0)));
+    pan.connect_drag_begin(clone!(
+        @weak image_scroll,
+        @strong tool_drop,
+        @strong pan_start
+            => move |pan, _, _| {
+                let button = pan.current_button();
+                if button == BUTTON_MIDDLE
+                    || (button == BUTTON_PRIMARY && Tool::from_selected(&tool_drop) == Tool::Pan)
+                {
+                    pan.set_state(EventSequenceState::Claimed);
+                    pan_start.set((
+                        image_scroll.hadjustment().value(),
+                        image_scroll.vadjustment().value(),
+                    ));
+                } else {
+                    pan.set_state(EventSequenceState::Denied);
+                }
+            }
+    ));
+    pan.connect_drag_update(
+        clone!(@weak image_scroll, @strong pan_start => move |_, x, y| {
+            let (start_x, start_y) = pan_start.get();
+            image_scroll.hadjustment().set_value(start_x - x);
+            image_scroll.vadjustment().set_value(start_y - y);
+        }),
+    );
+    image_scroll.add_controller(pan);
+}